@@ -1,8 +1,15 @@
-// This shader draws a circle with a given input color
+// This shader draws a circle with a given input color, covered by a clockwise cooldown sweep
 #import bevy_ui::ui_vertex_output::UiVertexOutput
 
+const PI: f32 = 3.14159265;
+
 struct CooldownUiMaterial {
-    @location(0) color: vec4<f32>
+    color: vec4<f32>,
+    rim_color: vec4<f32>,
+    glow_color: vec4<f32>,
+    progress: f32,
+    rim_width: f32,
+    glow_width: f32,
 }
 
 @group(1) @binding(0)
@@ -12,9 +19,43 @@ var<uniform> input: CooldownUiMaterial;
 fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
     // the UVs are now adjusted around the middle of the rect.
     let uv = in.uv * 2.0 - 1.0;
+    let distance = sqrt(dot(uv, uv));
 
     // circle alpha, the higher the power the harsher the falloff.
-    let alpha = (1.0 - pow(sqrt(dot(uv, uv)), 100.0)) * 0.9;
+    let circle = 1.0 - pow(distance, 100.0);
+
+    // angle measured clockwise from 12 o'clock, from 0.0 to 1.0 (UV y points down)
+    let angle = fract(atan2(uv.x, -uv.y) / (2.0 * PI) + 1.0);
+
+    // the covered wedge starts at the sweep edge and runs clockwise back to 12 o'clock,
+    // so it shrinks as the progress (remaining cooldown fraction) goes down.
+    // the smoothing is scaled by the distance so the edge stays the same width in pixels
+    let edge = 1.0 - clamp(input.progress, 0.0, 1.0);
+    let smoothing = 0.01 / max(distance, 0.01);
+    var sweep = smoothstep(edge - smoothing, edge + smoothing, angle);
+    if input.progress >= 1.0 {
+        sweep = 1.0;
+    } else if input.progress <= 0.0 {
+        sweep = 0.0;
+    }
+
+    var color = input.color.rgb;
+    var alpha = circle * sweep * input.color.a * 0.9;
+
+    // optional rim around the covered part, disabled by a zero width or alpha
+    if input.rim_width > 0.0 {
+        let rim = smoothstep(1.0 - input.rim_width - 0.02, 1.0 - input.rim_width, distance) * circle * sweep;
+        color = mix(color, input.rim_color.rgb, rim * input.rim_color.a);
+        alpha = max(alpha, rim * input.rim_color.a);
+    }
+
+    // optional glow along the leading edge of the sweep
+    if input.glow_width > 0.0 && input.progress > 0.0 && input.progress < 1.0 {
+        let edge_distance = abs(angle - edge) * 2.0 * PI * distance;
+        let glow = (1.0 - smoothstep(0.0, input.glow_width, edge_distance)) * circle * input.glow_color.a;
+        color = mix(color, input.glow_color.rgb, glow);
+        alpha = max(alpha, glow);
+    }
 
-    return vec4<f32>(input.color.rgb, alpha);
+    return vec4<f32>(color, alpha);
 }
@@ -1,32 +1,71 @@
-// This shader draws a solid plus-shaped crosshair with a given input color
+// This shader draws a plus-shaped crosshair with a center gap, optional dot and outline,
+// plus a diagonal hit-marker that flashes on confirmed hits
 #import bevy_ui::ui_vertex_output::UiVertexOutput
 
 struct CrosshairUiMaterial {
-    @location(0) color: vec4<f32>
+    color: vec4<f32>,
+    outline_color: vec4<f32>,
+    hit_marker_color: vec4<f32>,
+    thickness: f32, // Half the width of the lines
+    length: f32,    // Length of each line
+    gap: f32,       // Distance from the center to where the lines start
+    dot: f32,       // Radius of the center dot, 0.0 disables it
+    outline: f32,   // Width of the outline, 0.0 disables it
+    spread: f32,    // Extra gap added on top of the resting gap
+    hit_marker: f32, // Strength of the hit-marker, fades from 1.0 to 0.0
 }
 
 @group(1) @binding(0)
 var<uniform> input: CrosshairUiMaterial;
 
+// Signed distance to a box centered on the origin
+fn box_distance(point: vec2<f32>, half_size: vec2<f32>) -> f32 {
+    let d = abs(point) - half_size;
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
+}
+
+// Signed distance to four arms along the axes, starting at `start` from the center
+fn arms_distance(uv: vec2<f32>, start: f32, arm_length: f32, thickness: f32) -> f32 {
+    let middle = start + arm_length * 0.5;
+    let half_size = vec2<f32>(arm_length * 0.5, thickness);
+    let horizontal = box_distance(vec2<f32>(abs(uv.x) - middle, uv.y), half_size);
+    let vertical = box_distance(vec2<f32>(abs(uv.y) - middle, uv.x), half_size);
+    return min(horizontal, vertical);
+}
+
 @fragment
 fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
     // Adjust UVs to be centered on the rect, with a range from -1.0 to 1.0
     let uv = in.uv * 2.0 - 1.0;
 
-    // Crosshair parameters
-    let line_thickness = 0.02; // Thickness of the crosshair lines
-    let line_length = 5.0;     // Length of the crosshair lines in units (normalized)
+    // Crosshair shape, with the lines pushed out by the current spread
+    let start = max(input.gap + input.spread, 0.0);
+    var shape = arms_distance(uv, start, input.length, input.thickness);
+    if input.dot > 0.0 {
+        shape = min(shape, length(uv) - input.dot);
+    }
 
-    // Horizontal line condition (within line thickness and constrained to 5 units in length)
-    let horizontal_line = abs(uv.y) < line_thickness && abs(uv.x) < line_length / 10.0;
+    // Smooth the edges over roughly one pixel
+    let smoothing = max(fwidth(shape), 0.001);
+    let fill = 1.0 - smoothstep(-smoothing, smoothing, shape);
+    var color = input.color.rgb;
+    var alpha = fill * input.color.a;
 
-    // Vertical line condition (within line thickness and constrained to 5 units in length)
-    let vertical_line = abs(uv.x) < line_thickness && abs(uv.y) < line_length / 10.0;
+    // Outline drawn behind the crosshair
+    if input.outline > 0.0 {
+        let outline = (1.0 - smoothstep(-smoothing, smoothing, shape - input.outline)) * input.outline_color.a;
+        color = mix(input.outline_color.rgb, color, fill);
+        alpha = max(alpha, outline);
+    }
 
-    // If either the horizontal or vertical line condition is true, render the crosshair.
-    let crosshair_alpha = select(0.0, 0.9, horizontal_line || vertical_line);
+    // Hit-marker, the same arms rotated by 45 degrees and placed just outside the crosshair
+    if input.hit_marker > 0.0 {
+        let rotated = vec2<f32>(uv.x + uv.y, uv.x - uv.y) * 0.70710678;
+        let marker = arms_distance(rotated, start + input.length * 0.25, input.length * 0.6, input.thickness);
+        let marker_alpha = (1.0 - smoothstep(-smoothing, smoothing, marker)) * input.hit_marker * input.hit_marker_color.a;
+        color = mix(color, input.hit_marker_color.rgb, marker_alpha);
+        alpha = max(alpha, marker_alpha);
+    }
 
-    // Return the crosshair color with transparency
-    return vec4<f32>(input.color.rgb, crosshair_alpha);
+    return vec4<f32>(color, alpha);
 }
-
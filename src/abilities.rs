@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AbilityId {
+    PowerShot,
+    MultiShot,
+    PiercingShot,
+}
+
+impl AbilityId {
+    // Abilities without an icon get a disc in their projectile color with their initial
+    pub fn icon(&self) -> Option<&'static str> {
+        match self {
+            AbilityId::PowerShot => Some("images/power_shot.png"),
+            AbilityId::MultiShot | AbilityId::PiercingShot => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            AbilityId::PowerShot => Color::srgb_u8(255, 0, 0),
+            AbilityId::MultiShot => Color::srgb_u8(0, 0, 255),
+            AbilityId::PiercingShot => Color::srgb_u8(170, 0, 255),
+        }
+    }
+
+    pub fn initial(&self) -> &'static str {
+        match self {
+            AbilityId::PowerShot => "P",
+            AbilityId::MultiShot => "M",
+            AbilityId::PiercingShot => "X",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityActivation {
+    // Triggered by releasing a shot charged into the power tier
+    ChargedRelease,
+    Key(KeyCode),
+}
+
+impl AbilityActivation {
+    // Short label for the hotbar, e.g. "Q" for KeyCode::KeyQ
+    pub fn label(&self) -> String {
+        match self {
+            AbilityActivation::ChargedRelease => "Charge".to_string(),
+            AbilityActivation::Key(key) => {
+                let name = format!("{:?}", key);
+                name.strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AbilityEffect {
+    // The fully charged torus, spawned by player_shoot itself
+    PowerShot,
+    // Several quick arrows fanned out around the aim direction
+    Spread { arrows: u32, angle: f32 },
+    // A single fast arrow that keeps going through enemies
+    Piercing { pierce: u32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct Ability {
+    pub id: AbilityId,
+    pub activation: AbilityActivation,
+    pub effect: AbilityEffect,
+    pub cooldown: Timer, // Time until the next charge is restored
+    pub max_charges: u32,
+    pub charges: u32,
+}
+
+impl Ability {
+    pub fn new(
+        id: AbilityId,
+        activation: AbilityActivation,
+        effect: AbilityEffect,
+        cooldown_secs: f32,
+        max_charges: u32,
+    ) -> Self {
+        Self {
+            id,
+            activation,
+            effect,
+            cooldown: Timer::from_seconds(cooldown_secs, TimerMode::Once),
+            max_charges,
+            charges: max_charges,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.charges > 0
+    }
+
+    // Spend a charge, starting the recharge if the ability was full
+    pub fn try_use(&mut self) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+
+        if self.charges == self.max_charges {
+            self.cooldown.reset();
+        }
+        self.charges -= 1;
+        true
+    }
+
+    // Recharge one charge per cooldown until the ability is full again
+    pub fn tick(&mut self, delta: Duration) {
+        if self.charges >= self.max_charges {
+            return;
+        }
+
+        self.cooldown.tick(delta);
+        if self.cooldown.finished() {
+            self.charges += 1;
+            if self.charges < self.max_charges {
+                self.cooldown.reset();
+            }
+        }
+    }
+
+    pub fn is_recharging(&self) -> bool {
+        self.charges < self.max_charges
+    }
+
+    // Seconds until the next charge is restored, zero when the ability is full
+    pub fn remaining_secs(&self) -> f32 {
+        if self.is_recharging() {
+            self.cooldown.remaining_secs()
+        } else {
+            0.0
+        }
+    }
+
+    // Fraction of the current recharge still to go, from 1.0 just after use down to 0.0
+    pub fn remaining_fraction(&self) -> f32 {
+        if self.is_recharging() {
+            1.0 - self.cooldown.fraction()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Abilities(pub Vec<Ability>);
+
+impl Abilities {
+    pub fn get(&self, id: AbilityId) -> Option<&Ability> {
+        self.0.iter().find(|ability| ability.id == id)
+    }
+
+    // Abilities without an entry are treated as always available
+    pub fn is_ready(&self, id: AbilityId) -> bool {
+        self.get(id).is_none_or(Ability::is_ready)
+    }
+}
+
+impl Default for Abilities {
+    fn default() -> Self {
+        Self(vec![
+            Ability::new(
+                AbilityId::PowerShot,
+                AbilityActivation::ChargedRelease,
+                AbilityEffect::PowerShot,
+                3.0,
+                1,
+            ),
+            Ability::new(
+                AbilityId::MultiShot,
+                AbilityActivation::Key(KeyCode::KeyQ),
+                AbilityEffect::Spread {
+                    arrows: 5,
+                    angle: 0.5,
+                },
+                6.0,
+                2,
+            ),
+            Ability::new(
+                AbilityId::PiercingShot,
+                AbilityActivation::Key(KeyCode::KeyE),
+                AbilityEffect::Piercing { pierce: 3 },
+                8.0,
+                1,
+            ),
+        ])
+    }
+}
@@ -1,34 +1,44 @@
 use bevy::prelude::*;
 
+use crate::components::{WarningSign, WarningSignAnimation};
+
+// Root node of an off-screen threat indicator, the "!" and the arrow are spawned as children
 #[derive(Bundle)]
 pub struct WarningSignBundle {
     #[bundle()]
-    ui_bundle: TextBundle,
+    ui_bundle: NodeBundle,
+    warning_sign: WarningSign,
+    animation: WarningSignAnimation,
 }
 
 impl WarningSignBundle {
-    pub fn new(position: Vec2) -> Self {
+    pub fn new(enemy_entity: Entity, size: f32) -> Self {
         Self {
-            ui_bundle: TextBundle {
-                text: Text {
-                    sections: vec![TextSection {
-                        value: "!".to_string(),
-                        style: TextStyle {
-                            font: Default::default(),
-                            font_size: 50.0,
-                            color: Color::srgb(1.0, 0.0, 0.0),
-                        },
-                    }],
-                    ..Default::default()
-                },
+            ui_bundle: NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
-                    left: Val::Px(position.x),
-                    top: Val::Px(position.y),
+                    width: Val::Px(size),
+                    height: Val::Px(size),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
                     ..Default::default()
                 },
+                // Hidden until update_warning_positions has placed it
+                visibility: Visibility::Hidden,
                 ..Default::default()
             },
+            warning_sign: WarningSign {
+                enemy_entity: Some(enemy_entity),
+                screen_position: None,
+                scale: 1.0,
+            },
+            animation: WarningSignAnimation {
+                fade: 0.0,
+                phase: 0.0,
+                threat: 0.0,
+                last_distance: None,
+                approach_speed: 0.0,
+            },
         }
     }
 }
@@ -1,4 +1,8 @@
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::abilities::AbilityId;
 
 #[derive(Component)]
 pub struct Player {
@@ -8,13 +12,365 @@ pub struct Player {
     pub should_start_charge: bool,
     pub shoot_cooldown: Timer,
     pub score: u32,
+    pub pitch: f32, // Vertical aim in radians, positive looks up
+}
+
+impl Player {
+    pub const MIN_PITCH: f32 = -0.35;
+    pub const MAX_PITCH: f32 = 0.6;
+
+    // The player's rotation tilted up or down by the aim pitch
+    pub fn aim_rotation(&self, rotation: Quat) -> Quat {
+        rotation * Quat::from_rotation_x(self.pitch)
+    }
+
+    // How far the bow is drawn, from 0.0 to 1.0
+    pub fn charge_ratio(&self) -> f32 {
+        self.charge_timer
+            .elapsed_secs()
+            .min(self.max_charge_duration)
+            / self.max_charge_duration
+    }
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    pub invulnerability: Timer, // Runs after taking damage, no damage is taken until it finishes
+    pub since_damage: Stopwatch, // Regeneration only starts after a while without damage
+}
+
+impl Health {
+    pub const PLAYER_MAX: f32 = 100.0;
+    pub const INVULNERABILITY_SECS: f32 = 1.0;
+
+    pub fn new(max: f32) -> Self {
+        let mut invulnerability = Timer::from_seconds(Self::INVULNERABILITY_SECS, TimerMode::Once);
+        invulnerability.tick(invulnerability.duration());
+        Self {
+            current: max,
+            max,
+            invulnerability,
+            since_damage: Stopwatch::new(),
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerability.finished()
+    }
+
+    // Take damage unless invulnerable, returns whether it was taken
+    pub fn damage(&mut self, amount: f32) -> bool {
+        if self.is_invulnerable() {
+            return false;
+        }
+        self.current = (self.current - amount).max(0.0);
+        self.grant_invulnerability(Self::INVULNERABILITY_SECS);
+        self.since_damage.reset();
+        true
+    }
+
+    // Never cuts short a longer window that is still running
+    pub fn grant_invulnerability(&mut self, secs: f32) {
+        if secs <= self.invulnerability.remaining_secs() {
+            return;
+        }
+        self.invulnerability
+            .set_duration(Duration::from_secs_f32(secs));
+        self.invulnerability.reset();
+    }
+
+    // Invulnerable because of a recent hit rather than a dodge
+    pub fn is_recovering(&self) -> bool {
+        self.is_invulnerable() && self.since_damage.elapsed_secs() < Self::INVULNERABILITY_SECS
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+}
+
+#[derive(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    pub since_use: Stopwatch, // Stamina only comes back after a short break
+}
+
+impl Stamina {
+    pub const PLAYER_MAX: f32 = 100.0;
+    pub const SPRINT_COST_PER_SEC: f32 = 25.0;
+    pub const DODGE_COST: f32 = 30.0;
+    pub const REGEN_PER_SEC: f32 = 30.0;
+    pub const REGEN_DELAY: f32 = 0.75;
+
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            since_use: Stopwatch::new(),
+        }
+    }
+
+    // Spend stamina if there is enough of it, returns whether it was spent
+    pub fn try_spend(&mut self, amount: f32) -> bool {
+        if self.current < amount {
+            return false;
+        }
+        self.current -= amount;
+        self.since_use.reset();
+        true
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+}
+
+#[derive(Component)]
+pub struct Dodge {
+    pub cooldown: Timer, // Time until the next dodge is allowed
+    pub roll: Timer,     // Runs while the player is rolling
+    pub direction: Vec3,
+}
+
+impl Dodge {
+    pub const COOLDOWN_SECS: f32 = 1.0;
+    pub const ROLL_SECS: f32 = 0.3;
+    pub const SPEED: f32 = 9.0;
+    pub const INVULNERABILITY_SECS: f32 = 0.35;
+
+    pub fn is_rolling(&self) -> bool {
+        !self.roll.finished()
+    }
+}
+
+impl Default for Dodge {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(Self::COOLDOWN_SECS, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        let mut roll = Timer::from_seconds(Self::ROLL_SECS, TimerMode::Once);
+        roll.tick(roll.duration());
+        Self {
+            cooldown,
+            roll,
+            direction: Vec3::ZERO,
+        }
+    }
 }
 
+#[derive(Component)]
+pub struct StaminaBarFill {}
+
+// Velocity pushing the player away from whatever hit them, decays over time
+#[derive(Component, Default)]
+pub struct Knockback {
+    pub velocity: Vec3,
+}
+
+#[derive(Component)]
+pub struct HealthBarFill {}
+
+#[derive(Component)]
+pub struct HealthBarText {}
+
 #[derive(Component)]
 pub struct ThirdPersonCamera {
-    pub offset: Vec3,
+    pub distance: f32, // Distance behind the player, changed with the scroll wheel
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub zoom_speed: f32,         // Distance change per scroll line
+    pub height: f32,             // Height above the player
+    pub shoulder_offset: f32,    // Sideways offset, mirrored when swapping shoulders
+    pub shoulder_side: f32,      // 1.0 for the right shoulder, -1.0 for the left
+    pub look_at_offset: Vec3,    // Point the camera looks at, relative to the player
+    pub position_smoothing: f32, // Higher follows the player more tightly, 0.0 snaps
+    pub rotation_smoothing: f32,
+    pub collision_margin: f32, // How far to stay in front of anything blocking the view
+    pub fov: f32,              // Vertical field of view in radians
+    pub aim_fov: f32,          // Field of view with the bow fully drawn
+    pub aim_distance: f32,     // Distance behind the player with the bow fully drawn
+    pub aim_smoothing: f32,    // How quickly the aim zoom follows the draw
+    pub aim_zoom: f32,         // Current aim zoom, from 0.0 to 1.0
+    pub eye_height: f32,       // Height above the player's center in first person
+    pub shake: CameraShake,
+}
+
+// Trauma-based shake, the shake grows with the square of the trauma which decays over time
+pub struct CameraShake {
+    pub trauma: f32, // From 0.0 to 1.0
+    pub decay: f32,  // Trauma lost per second
+    pub max_offset: f32,
+    pub max_angle: f32, // Largest roll in radians
+    pub frequency: f32,
+    pub applied_offset: Vec3, // Shake added last frame, removed before the camera moves again
+    pub applied_rotation: Quat,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: 0.25,
+            max_angle: 0.08,
+            frequency: 25.0,
+            applied_offset: Vec3::ZERO,
+            applied_rotation: Quat::IDENTITY,
+        }
+    }
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+impl Default for ThirdPersonCamera {
+    fn default() -> Self {
+        Self {
+            distance: 3.0,
+            min_distance: 1.5,
+            max_distance: 8.0,
+            zoom_speed: 0.5,
+            height: 0.5,
+            shoulder_offset: 0.5,
+            shoulder_side: 1.0,
+            look_at_offset: Vec3::new(0.5, 0.5, 0.0),
+            position_smoothing: 12.0,
+            rotation_smoothing: 15.0,
+            collision_margin: 0.2,
+            fov: std::f32::consts::FRAC_PI_4,
+            aim_fov: 0.45,
+            aim_distance: 1.6,
+            aim_smoothing: 10.0,
+            aim_zoom: 0.0,
+            eye_height: 0.2,
+            shake: CameraShake::default(),
+        }
+    }
+}
+
+impl ThirdPersonCamera {
+    // Camera position relative to the player, in the player's local space.
+    // Aiming pulls the camera in towards the shoulder
+    pub fn local_offset(&self) -> Vec3 {
+        let distance = self
+            .distance
+            .lerp(self.aim_distance.min(self.distance), self.aim_zoom);
+        Vec3::new(
+            self.shoulder_offset * self.shoulder_side,
+            self.height,
+            distance,
+        )
+    }
+
+    pub fn current_fov(&self) -> f32 {
+        self.fov.lerp(self.aim_fov, self.aim_zoom)
+    }
+
+    // Look-at point relative to the player, in the player's local space
+    pub fn local_look_at(&self) -> Vec3 {
+        Vec3::new(
+            self.look_at_offset.x * self.shoulder_side,
+            self.look_at_offset.y,
+            self.look_at_offset.z,
+        )
+    }
+}
+
+// A killed enemy playing its death animation. It no longer has an Enemy component, so
+// everything else already treats it as dead
+#[derive(Component)]
+pub struct Dying {
+    pub timer: Timer,
+    pub fall_axis: Vec3, // Horizontal axis the body tips over around
+    pub start: Transform,
+}
+
+impl Dying {
+    pub const DURATION_SECS: f32 = 0.6;
+
+    // Tips over away from `push`, the direction of the killing blow
+    pub fn new(start: Transform, push: Vec3) -> Self {
+        let fall_axis = Vec3::Y
+            .cross(push.with_y(0.0))
+            .try_normalize()
+            .unwrap_or(Vec3::X);
+        Self {
+            timer: Timer::from_seconds(Self::DURATION_SECS, TimerMode::Once),
+            fall_axis,
+            start,
+        }
+    }
+}
+
+// Something the camera should not clip through, approximated by a sphere
+#[derive(Component)]
+pub struct CameraObstacle {
+    pub radius: f32,
+}
+
+// Charge thresholds that decide which projectile gets fired
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargeTier {
+    Quick,
+    Medium,
+    Power,
+}
+
+impl ChargeTier {
+    pub const MEDIUM_THRESHOLD: f32 = 0.33;
+    pub const POWER_THRESHOLD: f32 = 0.66;
+
+    pub fn from_strength(strength: f32) -> Self {
+        if strength < Self::MEDIUM_THRESHOLD {
+            ChargeTier::Quick
+        } else if strength < Self::POWER_THRESHOLD {
+            ChargeTier::Medium
+        } else {
+            ChargeTier::Power
+        }
+    }
+
+    // Matches the projectile fired for the tier
+    pub fn color(&self) -> Color {
+        match self {
+            ChargeTier::Quick => Color::srgb_u8(0, 0, 255),
+            ChargeTier::Medium => Color::srgb_u8(0, 255, 0),
+            ChargeTier::Power => Color::srgb_u8(255, 0, 0),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChargeTier::Quick => "Quick",
+            ChargeTier::Medium => "Medium",
+            ChargeTier::Power => "Power",
+        }
+    }
 }
 
+// Charge bar under the crosshair, shown while the player is drawing the bow
+#[derive(Component)]
+pub struct ChargeMeter {}
+
+#[derive(Component)]
+pub struct ChargeMeterFill {}
+
+#[derive(Component)]
+pub struct ChargeMeterText {}
+
 #[derive(Component)]
 pub struct Arrow {
     pub speed: f32,
@@ -22,13 +378,102 @@ pub struct Arrow {
     pub direction: Vec3,
     pub distance_travelled: f32,
     pub strength: f32,
+    pub pierce: u32,              // How many enemies the arrow can pass through
+    pub hit_enemies: Vec<Entity>, // Enemies already hit, so piercing arrows hit each only once
+}
+
+impl Arrow {
+    // Arrows that reach this height have hit the ground
+    pub const GROUND_HEIGHT: f32 = 0.0;
+
+    // The regular shot released after drawing the bow to `strength`
+    pub fn charged(direction: Vec3, strength: f32) -> Self {
+        Self {
+            speed: 10.0 * (1.0 / strength), // Adjust speed based on strength
+            range: 20.0,
+            direction,
+            distance_travelled: 0.0,
+            strength,
+            pierce: 0,
+            hit_enemies: Vec::new(),
+        }
+    }
+
+    // Any hit kills a full health enemy, crits deal bonus damage on top
+    pub fn damage(&self) -> f32 {
+        Enemy::MAX_HEALTH
+    }
+
+    // Move the arrow along its flight for `delta` seconds, returning its new position.
+    // Used for both real arrows and the trajectory preview so they never disagree
+    pub fn step(&mut self, position: Vec3, delta: f32) -> Vec3 {
+        self.distance_travelled += self.speed * delta;
+        position + self.direction * self.speed * delta
+    }
+
+    // Out of range or stuck in the ground
+    pub fn is_finished(&self, position: Vec3) -> bool {
+        self.distance_travelled >= self.range || position.y <= Self::GROUND_HEIGHT
+    }
+}
+
+// Arrows fired together by one ability use. A volley only counts as a miss if none of its
+// arrows hit anything
+#[derive(Component)]
+pub struct Volley {
+    pub id: Entity, // The volley's first arrow
+    pub hit: bool,
+}
+
+#[derive(Component)]
+pub struct Enemy {
+    pub health: f32,
+}
+
+// Link from an enemy in the danger zone to its warning sign, the other half of WarningSign
+#[derive(Component)]
+pub struct EnemyWarning {
+    pub sign: Entity,
+}
+
+impl Enemy {
+    pub const MAX_HEALTH: f32 = 1.0;
+    pub const HALF_SIZE: Vec3 = Vec3::splat(0.25);
+
+    // Every enemy is the same kind of zombie for now
+    pub fn minimap_color(&self) -> Color {
+        Color::srgb(1.0, 0.2, 0.2)
+    }
+}
+
+// Root of a hotbar slot, one per equipped ability
+#[derive(Component)]
+pub struct AbilitySlot {
+    pub ability: AbilityId,
+    pub last_charges: u32, // Used to notice when a charge comes back
+}
+
+#[derive(Component)]
+pub struct AbilityCooldownOverlay {
+    pub ability: AbilityId,
+}
+
+#[derive(Component)]
+pub struct AbilityCooldownText {
+    pub ability: AbilityId,
 }
 
 #[derive(Component)]
-pub struct Enemy {}
+pub struct AbilityChargePip {
+    pub ability: AbilityId,
+    pub index: u32,
+}
 
 #[derive(Component)]
-pub struct PowerShotCooldownUI {}
+pub struct AbilityReadyFlash {
+    pub ability: AbilityId,
+    pub timer: Timer,
+}
 
 #[derive(Component)]
 pub struct Ui2DComponent {
@@ -58,15 +503,93 @@ pub struct Ui2DText {
 }
 
 #[derive(Component)]
-pub struct Score {}
+pub struct Score {
+    pub pulse: Timer,                // Runs while the text is bumped after a change
+    pub base_font_size: Option<f32>, // Ui2DText font size before any pulse, read once
+}
 
 #[derive(Component)]
 pub struct WarningSign {
-    pub enemy_entity: Entity, // Link to the enemy associated with this warning
+    pub enemy_entity: Option<Entity>, // Link to the enemy, cleared once it leaves the danger zone
+    pub screen_position: Option<Vec2>, // None while the enemy is on screen
+    pub scale: f32,                   // Scale from the distance, before the pulse
+}
+
+#[derive(Component)]
+pub struct WarningSignAnimation {
+    pub fade: f32,   // 0.0 is fully transparent, 1.0 fully visible
+    pub phase: f32,  // Pulse phase in radians
+    pub threat: f32, // From 0.0 at the edge of the danger zone to 1.0 for a close, fast enemy
+    pub last_distance: Option<f32>,
+    pub approach_speed: f32, // Smoothed, in units per second
+}
+
+// Text of a warning sign that takes on its color and fade
+#[derive(Component)]
+pub struct WarningSignGlyph {
+    pub sign: Entity,
+}
+
+// Number of threats a warning sign stands for when several are clustered together
+#[derive(Component)]
+pub struct WarningSignCount {
+    pub sign: Entity,
+}
+
+#[derive(Component)]
+pub struct Minimap {}
+
+// Dot on the minimap following an enemy or an arrow
+#[derive(Component)]
+pub struct MinimapBlip {
+    pub target: Entity,
+}
+
+#[derive(Component)]
+pub struct MinimapPlayer {}
+
+// Static rectangle on the minimap, such as a ground tile or the arena bounds
+#[derive(Component)]
+pub struct MinimapShape {
+    pub center: Vec2, // World position on the XZ plane
+    pub size: Vec2,
 }
 
+// Rotates around the warning sign so the arrow points at the enemy
 #[derive(Component)]
-pub struct WarningSignAnimation {}
+pub struct WarningSignArrow {}
 
 #[derive(Component)]
 pub struct GameOverText {}
+
+#[derive(Component)]
+pub struct SettingsMenuText {}
+
+#[derive(Component)]
+pub struct LeaderboardText {}
+
+#[derive(Component)]
+pub struct RunSummaryText {}
+
+#[derive(Component)]
+pub struct Crosshair {
+    pub flash: Timer,      // Runs while the crosshair is flashing after a critical hit
+    pub hit_marker: Timer, // Runs while the hit-marker is shown after a hit
+    pub spread: f32,       // Current extra gap, eased towards the target every frame
+    pub last_player_position: Option<Vec3>, // Used to tell whether the player is moving
+}
+
+// Text that drifts upwards from a point in the world and fades out. The entities are
+// pooled, finished ones stay hidden until they are reused
+#[derive(Component)]
+pub struct FloatingText {
+    pub world_position: Vec3,
+    pub lifetime: Timer,
+    pub font_size: f32, // In the reference resolution, scaled like the rest of the UI
+}
+
+// Bow held in front of the first-person camera, pulled back while drawing
+#[derive(Component)]
+pub struct BowViewmodel {
+    pub rest_position: Vec3,
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use crate::{abilities::AbilityId, components::ChargeTier, particles::ParticleEmitter};
+
+#[derive(Event)]
+pub struct ShotFired {
+    pub tier: ChargeTier,
+    pub ability: Option<AbilityId>, // The ability that fired the arrow, if any
+}
+
+#[derive(Event)]
+pub struct ArrowHit {
+    pub arrow: Entity,
+    pub position: Vec3,   // Point on the enemy's bounds where the arrow struck
+    pub tier: ChargeTier, // Tier matching the arrow's strength
+    pub damage: f32,
+    pub critical: bool,
+    pub killed: bool,
+}
+
+// Sent when an enemy hurts the player
+#[derive(Event)]
+pub struct PlayerDamaged {
+    pub amount: f32,
+}
+
+// Sent when an arrow reaches the end of its range without hitting anything
+#[derive(Event)]
+pub struct ArrowMissed;
+
+// Emit a burst of particles at a point, `direction` is the center of the emitter's spread
+#[derive(Event)]
+pub struct SpawnParticles {
+    pub emitter: ParticleEmitter,
+    pub position: Vec3,
+    pub direction: Vec3,
+}
+
+// Show a short text rising from a point in the world, like damage or points
+#[derive(Event)]
+pub struct SpawnFloatingText {
+    pub position: Vec3,
+    pub text: String,
+    pub color: Color,
+    pub font_size: f32, // In the reference resolution
+}
@@ -0,0 +1,183 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::GameMode;
+
+const DATA_DIRECTORY: &str = "archery-minigame";
+const LEADERBOARD_FILE: &str = "leaderboard.ron";
+
+// Bump whenever the layout of LeaderboardEntry changes
+const LEADERBOARD_VERSION: u32 = 1;
+pub const MAX_LEADERBOARD_ENTRIES: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u32,
+    pub timestamp: u64, // Seconds since the unix epoch
+    pub seed: u64,
+    pub mode: GameMode,
+    pub duration_secs: f32,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Leaderboard {
+    version: u32,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Self {
+            version: LEADERBOARD_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl Leaderboard {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(DATA_DIRECTORY).join(LEADERBOARD_FILE))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<Leaderboard>(&contents) {
+            Ok(leaderboard) if leaderboard.version == LEADERBOARD_VERSION => leaderboard,
+            result => {
+                // Keep the unreadable file around instead of silently overwriting it
+                let backup = path.with_extension("ron.bak");
+                warn!(
+                    "Unsupported leaderboard file {} ({}), moving it to {}",
+                    path.display(),
+                    match result {
+                        Ok(leaderboard) => format!("version {}", leaderboard.version),
+                        Err(error) => error.to_string(),
+                    },
+                    backup.display()
+                );
+                let _ = fs::rename(&path, backup);
+                Self::default()
+            }
+        }
+    }
+
+    // Write to a temporary file first and rename it over the old one, so a crash
+    // halfway through never leaves a truncated leaderboard behind
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No data directory available, the leaderboard will not be saved");
+            return;
+        };
+
+        let result = || -> Result<(), Box<dyn std::error::Error>> {
+            let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let temporary_path = path.with_extension("ron.tmp");
+            let mut file = File::create(&temporary_path)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&temporary_path, &path)?;
+            Ok(())
+        }();
+
+        if let Err(error) = result {
+            warn!(
+                "Failed to save leaderboard to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_LEADERBOARD_ENTRIES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // Insert the entry in score order and return its rank, if it made the table
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_LEADERBOARD_ENTRIES {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_LEADERBOARD_ENTRIES);
+        Some(rank)
+    }
+
+    pub fn format_table(&self, highlight: Option<usize>) -> String {
+        let mut lines = vec!["High Scores".to_string()];
+        if self.entries.is_empty() {
+            lines.push("No scores yet".to_string());
+        }
+
+        for (rank, entry) in self.entries.iter().enumerate() {
+            let marker = if highlight == Some(rank) { ">" } else { " " };
+            lines.push(format!(
+                "{}{:>2}. {:<12} {:>5}  {}  {:>4.0}s  {}  #{:x}",
+                marker,
+                rank + 1,
+                entry.name,
+                entry.score,
+                format_date(entry.timestamp),
+                entry.duration_secs,
+                entry.mode.label(),
+                entry.seed
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Format a unix timestamp as YYYY-MM-DD (UTC) without pulling in a date crate
+fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's civil_from_days algorithm
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,26 +1,70 @@
+use abilities::{Abilities, AbilityActivation, AbilityEffect, AbilityId};
 use bevy::{
+    audio::Volume,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        mouse::{MouseScrollUnit, MouseWheel},
+        ButtonState,
+    },
+    pbr::NotShadowCaster,
     prelude::*,
     render::render_resource::{AsBindGroup, ShaderRef},
+    time::Stopwatch,
+    window::{PresentMode, WindowMode},
 };
 
 use bundles::WarningSignBundle;
 use components::{
-    Arrow, Enemy, GameOverText, Player, PowerShotCooldownUI, Score, ThirdPersonCamera,
-    Ui2DComponent, Ui2DText, WarningSign, WarningSignAnimation,
+    AbilityChargePip, AbilityCooldownOverlay, AbilityCooldownText, AbilityReadyFlash, AbilitySlot,
+    Arrow, BowViewmodel, CameraObstacle, ChargeMeter, ChargeMeterFill, ChargeMeterText, ChargeTier,
+    Crosshair, Dodge, Dying, Enemy, EnemyWarning, FloatingText, GameOverText, Health,
+    HealthBarFill, HealthBarText, Knockback, LeaderboardText, Minimap, MinimapBlip, MinimapPlayer,
+    MinimapShape, Player, RunSummaryText, Score, SettingsMenuText, Stamina, StaminaBarFill,
+    ThirdPersonCamera, Ui2DComponent, Ui2DText, Volley, WarningSign, WarningSignAnimation,
+    WarningSignArrow, WarningSignCount, WarningSignGlyph,
 };
-use rand::Rng;
-use resources::PowerShotCooldownTimer;
+use events::{ArrowHit, ArrowMissed, PlayerDamaged, ShotFired, SpawnFloatingText, SpawnParticles};
+use leaderboard::{unix_timestamp, Leaderboard, LeaderboardEntry, MAX_NAME_LENGTH};
+use particles::{Particle, ParticleEmitter, ParticlePool, ParticleTrail, MAX_PARTICLES};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use resources::{
+    Combo, FloatingTextPool, GameMode, HitStop, LeaderboardHighlight, NameEntry, RunInfo, RunRng,
+    RunStats, SettingsMenuSelection,
+};
+use settings::{CameraMode, Settings, SettingsOption};
 use states::GameState;
+use stats::RunRecord;
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::FRAC_PI_2,
+    time::Duration,
+};
 
+mod abilities;
 mod bundles;
 mod components;
+mod events;
+mod leaderboard;
+mod particles;
 mod resources;
+mod settings;
 mod states;
+mod stats;
 
 #[derive(AsBindGroup, Asset, TypePath, Debug, Clone)]
 struct CooldownUiMaterial {
     #[uniform(0)]
     color: Vec4,
+    #[uniform(0)]
+    rim_color: Vec4, // Alpha controls how strong the rim is
+    #[uniform(0)]
+    glow_color: Vec4, // Alpha controls how strong the glow is
+    #[uniform(0)]
+    progress: f32, // Remaining fraction of the cooldown, 1.0 covers the whole circle
+    #[uniform(0)]
+    rim_width: f32, // In UV units, 0.0 disables the rim
+    #[uniform(0)]
+    glow_width: f32, // In UV units, 0.0 disables the glow
 }
 
 impl UiMaterial for CooldownUiMaterial {
@@ -33,6 +77,52 @@ impl UiMaterial for CooldownUiMaterial {
 struct CrosshairUiMaterial {
     #[uniform(0)]
     color: Vec4,
+    #[uniform(0)]
+    outline_color: Vec4,
+    #[uniform(0)]
+    hit_marker_color: Vec4,
+    #[uniform(0)]
+    thickness: f32,
+    #[uniform(0)]
+    length: f32,
+    #[uniform(0)]
+    gap: f32,
+    #[uniform(0)]
+    dot: f32,
+    #[uniform(0)]
+    outline: f32,
+    #[uniform(0)]
+    spread: f32,
+    #[uniform(0)]
+    hit_marker: f32,
+}
+
+impl CrosshairUiMaterial {
+    fn from_settings(settings: &Settings) -> Self {
+        let mut material = Self {
+            color: CROSSHAIR_COLOR,
+            outline_color: Vec4::new(0.0, 0.0, 0.0, 0.6),
+            hit_marker_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            thickness: 0.0,
+            length: 0.0,
+            gap: 0.0,
+            dot: 0.0,
+            outline: 0.0,
+            spread: 0.0,
+            hit_marker: 0.0,
+        };
+        material.apply_settings(settings);
+        material
+    }
+
+    fn apply_settings(&mut self, settings: &Settings) {
+        let crosshair = &settings.crosshair;
+        self.thickness = crosshair.thickness;
+        self.length = crosshair.length;
+        self.gap = crosshair.gap;
+        self.dot = crosshair.dot;
+        self.outline = crosshair.outline;
+    }
 }
 
 impl UiMaterial for CrosshairUiMaterial {
@@ -41,25 +131,22 @@ impl UiMaterial for CrosshairUiMaterial {
     }
 }
 
-// Refactor: How to detect maximum screen resolution specific to the device?
+// Reference resolution the UI layout is designed for, also used as the default window size
 const MAX_WIDTH: f32 = 1536.0;
 const MAX_HEIGHT: f32 = 792.8;
 const MIN_WIDTH: f32 = 1080.0;
 const MIN_HEIGHT: f32 = MIN_WIDTH / (MAX_WIDTH / MAX_HEIGHT);
 
 fn main() {
-    // Set timer to be finished
-    let mut power_shot_cooldown_timer =
-        PowerShotCooldownTimer(Timer::from_seconds(3.0, TimerMode::Once));
-    power_shot_cooldown_timer
-        .0
-        .tick(power_shot_cooldown_timer.0.duration());
+    let settings = Settings::load();
 
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Archery".into(),
-                resolution: (MAX_WIDTH, MAX_HEIGHT).into(),
+                resolution: settings.display.resolution.into(),
+                mode: window_mode(&settings),
+                present_mode: present_mode(&settings),
                 resize_constraints: WindowResizeConstraints {
                     min_width: MIN_WIDTH,
                     min_height: MIN_HEIGHT,
@@ -73,44 +160,192 @@ fn main() {
             UiMaterialPlugin::<CooldownUiMaterial>::default(),
             UiMaterialPlugin::<CrosshairUiMaterial>::default(),
         ))
-        .insert_resource(power_shot_cooldown_timer)
+        .insert_resource(settings)
+        .insert_resource(Leaderboard::load())
+        .init_resource::<SettingsMenuSelection>()
+        .init_resource::<LeaderboardHighlight>()
+        .init_resource::<HitStop>()
+        .init_resource::<ParticlePool>()
+        .init_resource::<FloatingTextPool>()
         .init_state::<GameState>()
-        .add_systems(Startup, (setup, setup_player_score, game_over))
+        .add_event::<ShotFired>()
+        .add_event::<ArrowHit>()
+        .add_event::<PlayerDamaged>()
+        .add_event::<SpawnParticles>()
+        .add_event::<SpawnFloatingText>()
+        .add_event::<ArrowMissed>()
+        .add_systems(
+            Startup,
+            (
+                start_run,
+                setup,
+                setup_player_score,
+                setup_health_bar,
+                setup_minimap,
+                game_over,
+            ),
+        )
         .add_systems(
             Update,
             (
                 move_player,
-                (player_firing_arrows, player_arrow_charging, player_shoot).chain(),
-                arrow_movement,
-                update_power_cooldown_ui,
+                (
+                    toggle_camera_mode,
+                    update_camera,
+                    update_first_person_view,
+                    apply_camera_shake,
+                )
+                    .chain()
+                    .after(move_player)
+                    .after(update_player_health),
+                add_camera_trauma
+                    .after(hit_collision)
+                    .after(activate_abilities)
+                    .after(enemy_contact_damage)
+                    .before(apply_camera_shake),
+                (
+                    tick_ability_cooldowns,
+                    player_firing_arrows,
+                    player_arrow_charging,
+                    flash_invulnerable_player,
+                    update_charge_meter,
+                    player_shoot,
+                    activate_abilities,
+                )
+                    .chain(),
+                // After hit_collision, so an arrow that hits on its last frame is not also a miss
+                arrow_movement.after(hit_collision),
+                update_ability_hotbar,
                 random_spawn_enemies,
                 hit_collision,
-                update_player_score,
+                (
+                    enemy_contact_damage,
+                    update_player_health,
+                    update_health_bar,
+                )
+                    .chain()
+                    .after(enemies_walking),
                 enemies_walking,
-                check_enemy_proximity,
-                update_warning_positions,
-                remove_far_warning_signs,
+                animate_dying_enemies.after(hit_collision),
+                // After hit_collision, so enemies killed this frame don't get a new sign
+                (
+                    check_enemy_proximity,
+                    update_warning_positions,
+                    animate_warning_signs,
+                    remove_far_warning_signs,
+                )
+                    .chain()
+                    .after(hit_collision),
+                (
+                    open_settings_menu,
+                    toggle_leaderboard_menu,
+                    tick_run_duration,
+                ),
+                track_run_stats.after(hit_collision).after(player_shoot),
+                (update_combo, update_player_score)
+                    .chain()
+                    .after(hit_collision)
+                    .after(arrow_movement),
+                (flash_crosshair_on_hit, spawn_damage_numbers).after(hit_collision),
+                (update_crosshair, draw_trajectory_preview)
+                    .after(update_camera)
+                    .after(player_shoot),
+                (spawn_floating_text, update_floating_text)
+                    .chain()
+                    .after(update_combo)
+                    .after(spawn_damage_numbers),
+                (
+                    emit_charge_sparkles,
+                    emit_particle_trails,
+                    spawn_particles,
+                    update_particles,
+                )
+                    .chain()
+                    .after(hit_collision)
+                    .after(activate_abilities)
+                    .after(arrow_movement),
+                update_minimap.after(update_camera),
             )
                 .run_if(in_state(GameState::Playing)),
         )
-        .add_systems(Update, play_again.run_if(in_state(GameState::GameOver)))
-        .add_systems(Update, update_ui_2d)
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (record_game_over, clear_keyboard_events),
+        )
+        .add_systems(Update, update_hit_stop)
+        .add_systems(
+            Update,
+            (
+                name_entry_input.run_if(resource_exists::<NameEntry>),
+                play_again.run_if(not(resource_exists::<NameEntry>)),
+                update_leaderboard_text,
+            )
+                .chain()
+                .run_if(in_state(GameState::GameOver)),
+        )
+        .add_systems(OnEnter(GameState::Leaderboard), show_leaderboard)
+        .add_systems(OnExit(GameState::Leaderboard), hide_leaderboard)
+        .add_systems(
+            Update,
+            (toggle_leaderboard_menu, update_leaderboard_text)
+                .run_if(in_state(GameState::Leaderboard)),
+        )
+        .add_systems(OnEnter(GameState::Settings), spawn_settings_menu)
+        .add_systems(OnExit(GameState::Settings), despawn_settings_menu)
+        .add_systems(
+            Update,
+            (navigate_settings_menu, update_settings_menu_text)
+                .chain()
+                .run_if(in_state(GameState::Settings)),
+        )
+        .add_systems(Update, (apply_settings, spawn_ability_hotbar, update_ui_2d))
         .add_systems(
             Update,
-            (setup, setup_player_score, game_over)
+            (
+                start_run,
+                setup,
+                setup_player_score,
+                setup_health_bar,
+                setup_minimap,
+                game_over,
+            )
                 .chain()
                 .run_if(in_state(GameState::Restarting)),
         )
         .run();
 }
 
+fn window_mode(settings: &Settings) -> WindowMode {
+    if settings.display.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    }
+}
+
+fn present_mode(settings: &Settings) -> PresentMode {
+    if settings.display.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    }
+}
+
+// Top left, top right, bottom left and bottom right ground tiles
+const GROUND_TILES: [(Vec3, Color); 4] = [
+    (Vec3::new(3.0, 0.0, 1.5), Color::srgb(1.0, 0.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 1.5), Color::srgb(0.0, 1.0, 0.0)),
+    (Vec3::new(3.0, 0.0, -2.5), Color::srgb(0.0, 0.0, 1.0)),
+    (Vec3::new(-1.0, 0.0, -2.5), Color::srgb(1.0, 1.0, 0.0)),
+];
+const GROUND_TILE_HALF_SIZE: f32 = 2.0;
+
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    asset_server: Res<AssetServer>,
-    mut cooldown_ui_materials: ResMut<Assets<CooldownUiMaterial>>,
     mut crosshair_ui_materials: ResMut<Assets<CrosshairUiMaterial>>,
+    settings: Res<Settings>,
 ) {
     // Set cooldowns
     let mut shoot_cooldown = Timer::from_seconds(0.75, TimerMode::Once);
@@ -126,12 +361,21 @@ fn setup(
         },
         Player {
             is_charging: false,
-            charge_timer: Timer::from_seconds(1.0, TimerMode::Once),
-            max_charge_duration: 1.0,
+            charge_timer: Timer::from_seconds(
+                settings.gameplay.max_charge_duration,
+                TimerMode::Once,
+            ),
+            max_charge_duration: settings.gameplay.max_charge_duration,
             should_start_charge: false,
             shoot_cooldown,
             score: 0,
+            pitch: 0.0,
         },
+        Abilities::default(),
+        Health::new(Health::PLAYER_MAX),
+        Stamina::new(Stamina::PLAYER_MAX),
+        Dodge::default(),
+        Knockback::default(),
     ));
 
     // Light
@@ -144,122 +388,90 @@ fn setup(
         ..default()
     });
 
-    let image_handle = asset_server.load("images/power_shot.png");
+    // Camera, carrying the bow shown in first person
+    let bow_material = materials.add(Color::srgb_u8(120, 72, 36));
+    let string_material = materials.add(Color::srgb_u8(230, 230, 220));
+    let bow_rest_position = Vec3::new(0.25, -0.2, -0.6);
     commands
         .spawn((
-            ImageBundle {
-                style: Style {
-                    position_type: PositionType::Absolute,
-                    height: Val::Px(100.0),
-                    width: Val::Px(100.0),
-                    left: Val::Px(500.0),
-                    bottom: Val::Px(25.0),
-                    ..Default::default()
-                },
-                image: UiImage {
-                    texture: image_handle,
-                    ..Default::default()
-                },
-                ..Default::default()
+            Camera3dBundle {
+                transform: Transform::from_xyz(0.5, 1.0, 3.0),
+                ..default()
             },
-            BorderRadius {
-                top_left: Val::Px(50.0),
-                top_right: Val::Px(50.0),
-                bottom_left: Val::Px(50.0),
-                bottom_right: Val::Px(50.0),
-            },
-            Ui2DComponent::new(Vec2::new(500.0, 25.0), Vec2::new(100.0, 100.0)),
+            VisibilityBundle::default(),
+            ThirdPersonCamera::default(),
         ))
-        .with_children(|parent| {
-            parent
+        .with_children(|camera| {
+            camera
                 .spawn((
-                    MaterialNodeBundle {
-                        style: Style {
-                            position_type: PositionType::Absolute,
-                            width: Val::Px(105.),
-                            height: Val::Px(105.),
-                            ..default()
-                        },
-                        material: cooldown_ui_materials.add(CooldownUiMaterial {
-                            color: [0.15, 0.15, 0.15, 1.].into(),
-                        }),
+                    SpatialBundle {
+                        transform: Transform::from_translation(bow_rest_position),
                         visibility: Visibility::Hidden,
                         ..default()
                     },
-                    Ui2DComponent::new(Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0)),
-                    PowerShotCooldownUI {},
+                    BowViewmodel {
+                        rest_position: bow_rest_position,
+                    },
                 ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        TextBundle {
-                            style: Style {
-                                position_type: PositionType::Relative,
-                                ..default()
-                            },
-                            text: Text {
-                                sections: vec![TextSection {
-                                    value: "".to_string(),
-                                    ..Default::default()
-                                }],
-                                ..Default::default()
-                            },
-                            visibility: Visibility::Hidden,
-                            ..Default::default()
-                        },
-                        Ui2DComponent::new(Vec2::new(42.5, -33.0), Vec2::new(100.0, 100.0)),
-                        Ui2DText { font_size: 30.0 },
-                        PowerShotCooldownUI {},
-                    ));
+                .with_children(|bow| {
+                    // Grip, then the two limbs angled back towards the string
+                    bow.spawn(PbrBundle {
+                        mesh: meshes.add(Cuboid::new(0.03, 0.12, 0.03)),
+                        material: bow_material.clone(),
+                        ..default()
+                    });
+                    for side in [1.0, -1.0] {
+                        bow.spawn(PbrBundle {
+                            mesh: meshes.add(Cuboid::new(0.02, 0.22, 0.02)),
+                            material: bow_material.clone(),
+                            transform: Transform::from_xyz(0.0, side * 0.16, 0.03)
+                                .with_rotation(Quat::from_rotation_x(side * 0.35)),
+                            ..default()
+                        });
+                    }
+                    bow.spawn(PbrBundle {
+                        mesh: meshes.add(Cuboid::new(0.004, 0.5, 0.004)),
+                        material: string_material,
+                        transform: Transform::from_xyz(0.0, 0.0, 0.07),
+                        ..default()
+                    });
                 });
         });
 
-    // Camera
-    commands.spawn((
-        Camera3dBundle {
-            transform: Transform::from_xyz(0.5, 1.0, 3.0),
+    // Ground tiles
+    let plane = Plane3d::new(Vec3::new(0.0, 1.0, 0.0), Vec2::splat(GROUND_TILE_HALF_SIZE));
+    for (position, color) in GROUND_TILES {
+        commands.spawn(PbrBundle {
+            mesh: meshes.add(plane),
+            material: materials.add(color),
+            transform: Transform::from_translation(position),
             ..default()
-        },
-        ThirdPersonCamera {
-            offset: Vec3::new(0.5, 0.5, 3.0), // Offset matches the camera position
-        },
-    ));
-
-    // Plane
-    let plane = Plane3d::new(Vec3::new(0.0, 1.0, 0.0), Vec2::new(2.0, 2.0));
-
-    // Top left plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(plane),
-        material: materials.add(Color::srgb_u8(255, 0, 0)),
-        transform: Transform::from_xyz(3.0, 0.0, 1.5),
-        ..default()
-    });
-
-    // Top right plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(plane),
-        material: materials.add(Color::srgb_u8(0, 255, 0)),
-        transform: Transform::from_xyz(-1.0, 0.0, 1.5),
-        ..default()
-    });
-
-    // Bottom left plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(plane),
-        material: materials.add(Color::srgb_u8(0, 0, 255)),
-        transform: Transform::from_xyz(3.0, 0.0, -2.5),
-        ..default()
-    });
+        });
+    }
 
-    // Bottom right plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(plane),
-        material: materials.add(Color::srgb_u8(255, 255, 0)),
-        transform: Transform::from_xyz(-1.0, 0.0, -2.5),
-        ..default()
-    });
+    // Crosshair, positioned on the aim point by update_crosshair
+    let crosshair = commands
+        .spawn((
+            MaterialNodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                material: crosshair_ui_materials.add(CrosshairUiMaterial::from_settings(&settings)),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Crosshair {
+                flash: Timer::from_seconds(0.0, TimerMode::Once),
+                hit_marker: Timer::from_seconds(0.0, TimerMode::Once),
+                spread: 0.0,
+                last_player_position: None,
+            },
+        ))
+        .id();
 
-    // Crosshair
+    // Charge meter, with a tick at each tier threshold. A child of the crosshair so it stays
+    // centered under it
     commands
         .spawn((
             NodeBundle {
@@ -267,43 +479,96 @@ fn setup(
                     position_type: PositionType::Absolute,
                     ..Default::default()
                 },
-                background_color: BackgroundColor(Color::NONE),
+                background_color: BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                visibility: Visibility::Hidden,
+                border_radius: BorderRadius::all(Val::Px(3.0)),
                 ..Default::default()
             },
-            Ui2DComponent::new(Vec2::new(725.0, 325.0), Vec2::new(100.0, 100.0)),
+            Ui2DComponent::new(
+                Vec2::new(
+                    (CROSSHAIR_SIZE - CHARGE_METER_SIZE.x) / 2.0,
+                    CROSSHAIR_SIZE / 2.0 - CHARGE_METER_OFFSET - CHARGE_METER_SIZE.y,
+                ),
+                CHARGE_METER_SIZE,
+            ),
+            ChargeMeter {},
         ))
+        .set_parent(crosshair)
         .with_children(|parent| {
             parent.spawn((
-                MaterialNodeBundle {
+                NodeBundle {
                     style: Style {
-                        position_type: PositionType::Relative,
-                        width: Val::Px(50.),
-                        height: Val::Px(50.),
-                        ..default()
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
                     },
-                    material: crosshair_ui_materials.add(CrosshairUiMaterial {
-                        color: [0.5, 0.5, 0.5, 0.5].into(),
-                    }),
-                    ..default()
+                    border_radius: BorderRadius::all(Val::Px(3.0)),
+                    ..Default::default()
+                },
+                Ui2DComponent::new(Vec2::ZERO, Vec2::new(0.0, CHARGE_METER_SIZE.y)),
+                ChargeMeterFill {},
+            ));
+
+            for threshold in [ChargeTier::MEDIUM_THRESHOLD, ChargeTier::POWER_THRESHOLD] {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            ..Default::default()
+                        },
+                        background_color: BackgroundColor(Color::WHITE),
+                        ..Default::default()
+                    },
+                    Ui2DComponent::new(
+                        Vec2::new(CHARGE_METER_SIZE.x * threshold - 1.0, 0.0),
+                        Vec2::new(2.0, CHARGE_METER_SIZE.y),
+                    ),
+                ));
+            }
+
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    text: Text::from_section("", TextStyle::default())
+                        .with_justify(JustifyText::Center),
+                    ..Default::default()
                 },
-                Ui2DComponent::new(Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0)),
+                Ui2DComponent::new(Vec2::new(0.0, -24.0), Vec2::new(CHARGE_METER_SIZE.x, 20.0)),
+                Ui2DText { font_size: 18.0 },
+                ChargeMeterText {},
             ));
         });
 }
 
+// Movement tuning
+const SPRINT_MULTIPLIER: f32 = 1.75;
+const CHARGING_MULTIPLIER: f32 = 0.4;
+
 fn move_player(
-    mut player_query: Query<(&mut Transform, &Player), Without<Camera>>,
-    mut camera_query: Query<(&mut Transform, &ThirdPersonCamera), Without<Player>>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Player,
+            &mut Stamina,
+            &mut Dodge,
+            &mut Health,
+        ),
+        Without<Camera>,
+    >,
+    camera: Query<&ThirdPersonCamera>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
+    settings: Res<Settings>,
 ) {
-    // Player movement variables
-    let player_speed = 2.5;
-    let rotation_speed = 1.0; // Rotation speed in radians
-    let (mut player_transform, player) = player_query.single_mut();
-
-    // The point we will rotate and look at
-    let mut look_at_point = Vec3::new(0.5, 0.5, 0.0);
+    // Player movement variables, turning slows down while zoomed in for precise aiming
+    let player_speed = settings.input.movement_speed;
+    let aim_zoom = camera.get_single().map_or(0.0, |camera| camera.aim_zoom);
+    let rotation_speed =
+        settings.input.rotation_speed * 1.0.lerp(settings.input.aim_sensitivity, aim_zoom); // Rotation speed in radians
+    let (mut player_transform, mut player, mut stamina, mut dodge, mut health) =
+        player_query.single_mut();
 
     // Handle player rotation using ArrowLeft (left) and ArrowRight (right)
     if keyboard_input.pressed(KeyCode::ArrowLeft) {
@@ -315,54 +580,350 @@ fn move_player(
         ));
     }
 
-    // Rotate the look_at_point the same way as the player
-    let rotation_quat = player_transform.rotation;
-    look_at_point = rotation_quat.mul_vec3(look_at_point);
+    // Tilt the aim up with W and down with S, within limits
+    let mut pitch_input = 0.0;
+    if keyboard_input.pressed(KeyCode::KeyW) {
+        pitch_input += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::KeyS) {
+        pitch_input -= 1.0;
+    }
+    player.pitch = (player.pitch + pitch_input * rotation_speed * time.delta_seconds())
+        .clamp(Player::MIN_PITCH, Player::MAX_PITCH);
 
     // Calculate forward vector based on player rotation (looking direction)
     let forward = player_transform.forward();
 
-    // Handle player forward/backward movement using ArrowUp and ArrowDown
-    if keyboard_input.pressed(KeyCode::ArrowUp) && !player.is_charging {
-        player_transform.translation += forward * player_speed * time.delta_seconds();
+    let right = player_transform.right();
+
+    // Forward/backward movement using ArrowUp and ArrowDown, strafing using A and D
+    let mut movement = Vec3::ZERO;
+    if keyboard_input.pressed(KeyCode::ArrowUp) {
+        movement += *forward;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowDown) {
+        movement -= *forward;
+    }
+    if keyboard_input.pressed(KeyCode::KeyD) {
+        movement += *right;
+    }
+    if keyboard_input.pressed(KeyCode::KeyA) {
+        movement -= *right;
+    }
+    let movement = movement.normalize_or_zero();
+
+    dodge.cooldown.tick(time.delta());
+    dodge.roll.tick(time.delta());
+    stamina.since_use.tick(time.delta());
+
+    // Dodge-roll with LeftControl, in the movement direction or backwards when standing still
+    if keyboard_input.just_pressed(KeyCode::ControlLeft)
+        && !player.is_charging
+        && !dodge.is_rolling()
+        && dodge.cooldown.finished()
+        && stamina.try_spend(Stamina::DODGE_COST)
+    {
+        dodge.direction = if movement == Vec3::ZERO {
+            -*forward
+        } else {
+            movement
+        };
+        dodge.roll.reset();
+        dodge.cooldown.reset();
+        health.grant_invulnerability(Dodge::INVULNERABILITY_SECS);
+    }
+
+    if dodge.is_rolling() {
+        player_transform.translation += dodge.direction * Dodge::SPEED * time.delta_seconds();
+    } else if movement != Vec3::ZERO && (!player.is_charging || settings.input.move_while_charging)
+    {
+        // Sprint with LeftShift while there is stamina left, walk slowly while charging
+        let mut speed = player_speed;
+        if player.is_charging {
+            speed *= CHARGING_MULTIPLIER;
+        } else if keyboard_input.pressed(KeyCode::ShiftLeft)
+            && stamina.try_spend(Stamina::SPRINT_COST_PER_SEC * time.delta_seconds())
+        {
+            speed *= SPRINT_MULTIPLIER;
+        }
+        player_transform.translation += movement * speed * time.delta_seconds();
+    }
+
+    if stamina.since_use.elapsed_secs() >= Stamina::REGEN_DELAY {
+        stamina.current =
+            (stamina.current + Stamina::REGEN_PER_SEC * time.delta_seconds()).min(stamina.max);
+    }
+}
+
+// Lowest the camera may go, so it never dips under the ground
+const CAMERA_MIN_HEIGHT: f32 = 0.2;
+
+// Cycle through the camera modes with V
+fn toggle_camera_mode(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keyboard_input.just_pressed(KeyCode::KeyV) {
+        settings.display.camera_mode = settings.display.camera_mode.next();
+        settings.save();
+    }
+}
+
+// Swaps the player mesh for the bow viewmodel in first person, and draws the bow back
+// with the charge
+fn update_first_person_view(
+    mut player: Query<(&Player, &mut Visibility), Without<BowViewmodel>>,
+    mut bow: Query<(&BowViewmodel, &mut Transform, &mut Visibility), Without<Player>>,
+    settings: Res<Settings>,
+) {
+    let first_person = settings.display.camera_mode == CameraMode::FirstPerson;
+    let (player, mut player_visibility) = player.single_mut();
+    let (bow, mut bow_transform, mut bow_visibility) = bow.single_mut();
+
+    let (player_state, bow_state) = if first_person {
+        (Visibility::Hidden, Visibility::Inherited)
+    } else {
+        (Visibility::Inherited, Visibility::Hidden)
+    };
+    player_visibility.set_if_neq(player_state);
+    bow_visibility.set_if_neq(bow_state);
+
+    let draw = if player.is_charging {
+        player.charge_ratio()
+    } else {
+        0.0
+    };
+    bow_transform.translation = bow.rest_position + Vec3::new(-0.05, 0.0, 0.12) * draw;
+}
+
+// Follow the player from behind their shoulder, zooming with the scroll wheel,
+// swapping shoulders with Tab and pulling in when something blocks the view
+fn update_camera(
+    player: Query<(&Transform, &Player), Without<ThirdPersonCamera>>,
+    mut camera: Query<(&mut Transform, &mut Projection, &mut ThirdPersonCamera)>,
+    obstacles: Query<(&GlobalTransform, &CameraObstacle)>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let (player_transform, player) = player.single();
+    let (mut camera_transform, mut projection, mut camera) = camera.single_mut();
+
+    // Take last frame's shake back out so it does not feed into the smoothing
+    camera_transform.translation -= camera.shake.applied_offset;
+    camera_transform.rotation *= camera.shake.applied_rotation.inverse();
+
+    for event in mouse_wheel_events.read() {
+        // Pixel scrolling (touchpads) reports much larger values than line scrolling
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        };
+        camera.distance = (camera.distance - lines * camera.zoom_speed)
+            .clamp(camera.min_distance, camera.max_distance);
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        camera.shoulder_side = -camera.shoulder_side;
+    }
+
+    // Zoom in with the draw of the bow and back out on release
+    let target_zoom = if player.is_charging {
+        settings.input.aim_zoom * settings.input.aim_zoom_curve.apply(player.charge_ratio())
+    } else {
+        0.0
+    };
+    let zoom_easing = 1.0 - (-camera.aim_smoothing * time.delta_seconds()).exp();
+    camera.aim_zoom += (target_zoom - camera.aim_zoom) * zoom_easing;
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        perspective.fov = camera.current_fov();
+    }
+
+    // First person sits at the player's eyes and turns with them, without smoothing or
+    // collision
+    if settings.display.camera_mode == CameraMode::FirstPerson {
+        camera_transform.translation = player_transform.translation + Vec3::Y * camera.eye_height;
+        camera_transform.rotation = player.aim_rotation(player_transform.rotation);
+        return;
+    }
+
+    // Pitching orbits the camera around the look-at point, so looking up lowers the camera
+    let look_at = camera.local_look_at();
+    let target = player_transform.translation + player_transform.rotation * look_at;
+    let desired_position =
+        target + player.aim_rotation(player_transform.rotation) * (camera.local_offset() - look_at);
+
+    // Ease towards the desired position and rotation, framerate independent
+    let position_easing = if camera.position_smoothing > 0.0 {
+        1.0 - (-camera.position_smoothing * time.delta_seconds()).exp()
+    } else {
+        1.0
+    };
+    let mut position = camera_transform
+        .translation
+        .lerp(desired_position, position_easing);
+
+    // Pull the camera in front of the closest obstacle between it and the look-at point
+    let to_camera = position - target;
+    let length = to_camera.length();
+    if length > f32::EPSILON {
+        let direction = to_camera / length;
+        let mut allowed = length;
+        for (obstacle_transform, obstacle) in obstacles.iter() {
+            if let Some(hit) = ray_sphere_distance(
+                target,
+                direction,
+                obstacle_transform.translation(),
+                obstacle.radius + camera.collision_margin,
+            ) {
+                allowed = allowed.min(hit);
+            }
+        }
+        // The ground counts too, so looking up keeps the camera on the aim line
+        if direction.y < 0.0 {
+            allowed = allowed.min((CAMERA_MIN_HEIGHT - target.y) / direction.y);
+        }
+        position = target + direction * allowed;
+    }
+    position.y = position.y.max(CAMERA_MIN_HEIGHT);
+    camera_transform.translation = position;
+
+    let rotation_easing = if camera.rotation_smoothing > 0.0 {
+        1.0 - (-camera.rotation_smoothing * time.delta_seconds()).exp()
+    } else {
+        1.0
+    };
+    let desired_rotation = camera_transform.looking_at(target, Vec3::Y).rotation;
+    camera_transform.rotation = camera_transform
+        .rotation
+        .slerp(desired_rotation, rotation_easing);
+}
+
+// Trauma added by each kind of impact, before the shake intensity setting
+const TRAUMA_POWER_SHOT: f32 = 0.35;
+const TRAUMA_KILL: f32 = 0.25;
+const TRAUMA_PLAYER_DAMAGE: f32 = 0.6; // For a regular contact hit
+const HIT_STOP_SECS: f32 = 0.08;
+const HIT_STOP_SPEED: f32 = 0.05;
+
+fn add_camera_trauma(
+    mut camera: Query<&mut ThirdPersonCamera>,
+    mut shot_fired_events: EventReader<ShotFired>,
+    mut arrow_hit_events: EventReader<ArrowHit>,
+    mut player_damaged_events: EventReader<PlayerDamaged>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    settings: Res<Settings>,
+) {
+    let mut camera = camera.single_mut();
+    let accessibility = &settings.accessibility;
+
+    for event in shot_fired_events.read() {
+        if event.tier == ChargeTier::Power {
+            camera.shake.add_trauma(TRAUMA_POWER_SHOT);
+        }
+    }
+    for event in arrow_hit_events.read() {
+        if !event.killed {
+            continue;
+        }
+        camera.shake.add_trauma(TRAUMA_KILL);
+
+        // Freeze the action for a moment when a charged shot lands a kill
+        if event.tier != ChargeTier::Quick
+            && accessibility.hit_stop
+            && !accessibility.reduced_motion
+        {
+            hit_stop.remaining = HIT_STOP_SECS;
+            virtual_time.set_relative_speed(HIT_STOP_SPEED);
+        }
+    }
+    // Scaled by the damage, so a hardcore death shakes harder than a regular hit
+    for event in player_damaged_events.read() {
+        camera
+            .shake
+            .add_trauma(TRAUMA_PLAYER_DAMAGE * event.amount / ENEMY_CONTACT_DAMAGE);
     }
-    if keyboard_input.pressed(KeyCode::ArrowDown) && !player.is_charging {
-        player_transform.translation -= forward * player_speed * time.delta_seconds();
+}
+
+// Runs in every state so time always gets back to normal speed
+fn update_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    if hit_stop.remaining <= 0.0 {
+        return;
     }
 
-    // Camera logic
-    let (mut camera_transform, camera) = camera_query.single_mut();
+    hit_stop.remaining -= real_time.delta_seconds();
+    if hit_stop.remaining <= 0.0 {
+        virtual_time.set_relative_speed(1.0);
+    }
+}
 
-    // Maintain camera's offset relative to the player
-    let rotated_offset = rotation_quat.mul_vec3(camera.offset);
+// Shake the camera on top of where update_camera put it
+fn apply_camera_shake(
+    mut camera: Query<(&mut Transform, &mut ThirdPersonCamera)>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let (mut transform, mut camera) = camera.single_mut();
+    let shake = &mut camera.shake;
+    shake.trauma = (shake.trauma - shake.decay * time.delta_seconds()).max(0.0);
 
-    // Set the camera's position based on player's position and rotated offset
-    camera_transform.translation = player_transform.translation + rotated_offset;
+    let intensity = if settings.accessibility.reduced_motion {
+        0.0
+    } else {
+        settings.accessibility.screen_shake
+    };
+    let amount = shake.trauma * shake.trauma * intensity;
+
+    // Sines at unrelated frequencies stand in for noise
+    let t = time.elapsed_seconds() * shake.frequency;
+    let offset = transform.rotation
+        * Vec3::new(t.sin(), (t * 1.31 + 1.7).sin(), 0.0)
+        * shake.max_offset
+        * amount;
+    let rotation = Quat::from_rotation_z((t * 0.87 + 4.2).sin() * shake.max_angle * amount);
+
+    transform.translation += offset;
+    transform.rotation *= rotation;
+    shake.applied_offset = offset;
+    shake.applied_rotation = rotation;
+}
 
-    // Ensure the camera is looking at the rotated look_at_point
-    camera_transform.look_at(player_transform.translation + look_at_point, Vec3::Y);
+// Distance along a ray to where it enters a sphere, if it does so ahead of the origin.
+// Spheres around the origin are ignored, otherwise anything touching the player would
+// pull the camera into them
+fn ray_sphere_distance(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let to_center = center - origin;
+    let along = to_center.dot(direction);
+    let closest_squared = to_center.length_squared() - along * along;
+    let radius_squared = radius * radius;
+    if along < 0.0
+        || closest_squared > radius_squared
+        || to_center.length_squared() < radius_squared
+    {
+        return None;
+    }
+    Some(along - (radius_squared - closest_squared).sqrt())
 }
 
 fn player_firing_arrows(
     mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
-    mut players: Query<&mut Player>,
+    mut players: Query<(&mut Player, &Abilities)>,
     time: Res<Time>,
-    mut power_shot_cooldown_timer: ResMut<PowerShotCooldownTimer>,
 ) {
-    let mut player = players.single_mut();
+    let (mut player, abilities) = players.single_mut();
+    let power_shot_ready = abilities.is_ready(AbilityId::PowerShot);
 
     // If cooldowns are not finished, tick the timers
     if !player.shoot_cooldown.finished() {
         player.shoot_cooldown.tick(time.delta());
     }
-    if !power_shot_cooldown_timer.0.finished() {
-        power_shot_cooldown_timer.0.tick(time.delta());
-    }
 
     // If the space key is held down and the player is charging, set the flag
     if keyboard_input.clear_just_pressed(KeyCode::Space) {
         // Set the charge flag even if cooldown is active
-        if !player.shoot_cooldown.finished() || !power_shot_cooldown_timer.0.finished() {
+        if !player.shoot_cooldown.finished() || !power_shot_ready {
             player.should_start_charge = true; // Keep track of the pending charge
         } else {
             // Start charging if cooldowns are done
@@ -378,13 +939,11 @@ fn player_firing_arrows(
     }
 
     // Automatically start charging if the cooldowns finish and the spacebar is still held
-    if player.shoot_cooldown.finished() && power_shot_cooldown_timer.0.finished() {
-        if player.should_start_charge {
-            // Start charging automatically after cooldown finishes
-            player.is_charging = true;
-            player.charge_timer.reset();
-            player.should_start_charge = false; // Clear the flag
-        }
+    if player.shoot_cooldown.finished() && power_shot_ready && player.should_start_charge {
+        // Start charging automatically after cooldown finishes
+        player.is_charging = true;
+        player.charge_timer.reset();
+        player.should_start_charge = false; // Clear the flag
     }
 }
 
@@ -419,168 +978,690 @@ fn player_arrow_charging(
     }
 }
 
-fn player_shoot(
-    mut commands: Commands,
-    mut player: Query<(&Transform, &mut Player), Without<Camera>>,
-    camera: Query<(&Transform, &Camera, &ThirdPersonCamera), Without<Player>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut power_shot_cooldown_timer: ResMut<PowerShotCooldownTimer>,
+// Charge meter size, in the reference resolution
+const CHARGE_METER_SIZE: Vec2 = Vec2::new(160.0, 12.0);
+// Distance from the crosshair's center down to the top of the charge meter
+const CHARGE_METER_OFFSET: f32 = 80.0;
+
+// Fill the charge meter with the current draw strength, colored by the tier it would fire
+fn update_charge_meter(
+    player: Query<&Player>,
+    mut meter: Query<&mut Visibility, With<ChargeMeter>>,
+    mut fill: Query<(&mut Ui2DComponent, &mut BackgroundColor), With<ChargeMeterFill>>,
+    mut text: Query<&mut Text, With<ChargeMeterText>>,
+    time: Res<Time>,
 ) {
-    let (player_transform, mut player) = player.single_mut();
-    let (camera_transform, _, _) = camera.single();
+    let player = player.single();
+    let mut visibility = meter.single_mut();
 
-    // Get the player's position and rotation
-    let player_translation = player_transform.translation;
-    let player_rotation = player_transform.rotation;
+    if !player.is_charging {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let strength = player.charge_ratio();
+    let tier = ChargeTier::from_strength(strength);
+    let (mut fill_component, mut fill_color) = fill.single_mut();
+    let mut text = text.single_mut();
+
+    fill_component.base_size.x = CHARGE_METER_SIZE.x * strength;
+    if strength >= 1.0 {
+        // Pulse between the tier color and gold once fully drawn
+        let pulse = (time.elapsed_seconds() * 10.0).sin() * 0.5 + 0.5;
+        fill_color.0 = tier.color().mix(&Color::srgb(1.0, 0.85, 0.2), pulse);
+        text.sections[0].value = format!("{} - MAX", tier.label());
+    } else {
+        fill_color.0 = tier.color();
+        text.sections[0].value = tier.label().to_string();
+    }
+}
 
-    // Offset to spawn the projectile in front of the player
-    let spawn_offset = player_rotation * -Vec3::Z; // Forward direction (assuming Z is forward)
-    let spawn_position = player_translation + spawn_offset * 1.0; // Adjust the multiplier for distance from the player
+// Define color and shape based on charge level
+fn projectile_appearance(tier: ChargeTier, meshes: &mut Assets<Mesh>) -> (Color, Handle<Mesh>) {
+    match tier {
+        ChargeTier::Quick => (tier.color(), meshes.add(Mesh::from(Sphere { radius: 0.2 }))),
+        ChargeTier::Medium => (
+            tier.color(),
+            meshes.add(Mesh::from(Cuboid {
+                half_size: Vec3::splat(0.1),
+            })),
+        ),
+        ChargeTier::Power => (
+            tier.color(),
+            meshes.add(Mesh::from(Torus {
+                minor_radius: 0.1,
+                major_radius: 0.2,
+            })),
+        ),
+    }
+}
 
-    if !player.is_charging && player.charge_timer.elapsed_secs() > 0.0 {
-        // Calculate strength based on charge time
-        let charge_time = player
-            .charge_timer
-            .elapsed_secs()
-            .min(player.max_charge_duration);
-        let strength = charge_time / player.max_charge_duration;
+// Spawn position in front of the player and the direction they are aiming in, which is
+// also the direction the camera looks along
+fn shot_origin(
+    player_transform: &Transform,
+    player: &Player,
+    camera_transform: &Transform,
+    settings: &Settings,
+) -> (Vec3, Vec3) {
+    let aim_rotation = player.aim_rotation(player_transform.rotation);
+    let aim_forward = aim_rotation * -Vec3::Z;
+
+    // In first person the arrow leaves from in front of the eyes, straight along the view
+    if settings.display.camera_mode == CameraMode::FirstPerson {
+        return (
+            camera_transform.translation + aim_forward * 0.6,
+            aim_forward,
+        );
+    }
 
-        // Define color and shape based on charge level
-        let (projectile_color, projectile_mesh): (Color, Handle<Mesh>) = if strength < 0.33 {
-            (
-                Color::srgb_u8(0, 0, 255),
-                meshes.add(Mesh::from(Sphere { radius: 0.2 })),
-            )
-        } else if strength < 0.66 {
-            (
-                Color::srgb_u8(0, 255, 0),
-                meshes.add(Mesh::from(Cuboid {
-                    half_size: Vec3::splat(0.1),
-                })),
-            )
-        } else {
-            (
-                Color::srgb_u8(255, 0, 0),
-                meshes.add(Mesh::from(Torus {
-                    minor_radius: 0.1,
-                    major_radius: 0.2,
-                })),
-            )
-        };
+    // Offset to spawn the projectile in front of the player
+    let spawn_offset = player_transform.rotation * -Vec3::Z; // Forward direction (assuming Z is forward)
+    let spawn_position = player_transform.translation + spawn_offset * 1.0; // Adjust the multiplier for distance from the player
 
-        let camera_forward = camera_transform.forward();
-        let camera_right = camera_transform.right();
+    // Adjust by adding a small amount of the right direction
+    let direction = (aim_forward + aim_rotation * Vec3::X * settings.input.aim_offset).normalize();
 
-        // Adjust by adding a small amount of the right direction
-        let adjusted_forward =
-            Dir3::new_unchecked((camera_forward.as_vec3() + camera_right * 0.03).normalize());
+    (spawn_position, direction)
+}
 
-        // Spawn the projectile at the calculated position
-        commands
-            .spawn(PbrBundle {
-                mesh: projectile_mesh,
-                material: materials.add(projectile_color),
+fn spawn_arrow(
+    commands: &mut Commands,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    position: Vec3,
+    arrow: Arrow,
+) -> Entity {
+    commands
+        .spawn((
+            PbrBundle {
+                mesh,
+                material,
                 transform: Transform {
-                    translation: spawn_position,
-                    rotation: Quat::from_rotation_arc(Vec3::Z, *adjusted_forward), // Set rotation to face the camera's forward direction
+                    translation: position,
+                    rotation: Quat::from_rotation_arc(Vec3::Z, arrow.direction), // Face the direction of travel
                     ..Default::default()
                 },
                 ..Default::default()
-            })
-            .insert(Arrow {
-                speed: 10.0 * (1.0 / strength), // Adjust speed based on strength
-                range: 20.0,
-                direction: *adjusted_forward, // Use the camera's forward direction instead of spawn_offset
-                distance_travelled: 0.0,
-                strength,
-            });
+            },
+            arrow,
+        ))
+        .id()
+}
 
-        player.charge_timer.reset();
+// Spawn the arrows for an ability, sending a ShotFired event for each of them
+#[allow(clippy::too_many_arguments)]
+fn fire_ability_effect(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    shot_fired_events: &mut EventWriter<ShotFired>,
+    ability: AbilityId,
+    effect: AbilityEffect,
+    position: Vec3,
+    direction: Vec3,
+    strength: f32, // How far the bow was drawn, the power shot keeps the released strength
+) {
+    match effect {
+        AbilityEffect::PowerShot => {
+            let (color, mesh) = projectile_appearance(ChargeTier::Power, meshes);
+            let arrow = spawn_arrow(
+                commands,
+                mesh,
+                materials.add(color),
+                position,
+                Arrow::charged(direction, strength),
+            );
+            commands
+                .entity(arrow)
+                .insert(ParticleTrail::new(ParticleEmitter::trail(color), 0.02));
+            shot_fired_events.send(ShotFired {
+                tier: ChargeTier::Power,
+                ability: Some(ability),
+            });
+        }
+        AbilityEffect::Spread { arrows, angle } => {
+            let (color, mesh) = projectile_appearance(ChargeTier::Quick, meshes);
+            let material = materials.add(color);
+
+            // Fan the arrows out evenly around the aim direction
+            let mut volley = None;
+            for index in 0..arrows {
+                let offset = if arrows > 1 {
+                    -angle / 2.0 + angle * index as f32 / (arrows - 1) as f32
+                } else {
+                    0.0
+                };
+                let arrow = spawn_arrow(
+                    commands,
+                    mesh.clone(),
+                    material.clone(),
+                    position,
+                    Arrow {
+                        speed: 15.0,
+                        range: 15.0,
+                        direction: Quat::from_rotation_y(offset) * direction,
+                        distance_travelled: 0.0,
+                        strength: 0.2,
+                        pierce: 0,
+                        hit_enemies: Vec::new(),
+                    },
+                );
+                commands.entity(arrow).insert(Volley {
+                    id: *volley.get_or_insert(arrow),
+                    hit: false,
+                });
+                shot_fired_events.send(ShotFired {
+                    tier: ChargeTier::Quick,
+                    ability: Some(ability),
+                });
+            }
+        }
+        AbilityEffect::Piercing { pierce } => {
+            spawn_arrow(
+                commands,
+                meshes.add(Cuboid::new(0.08, 0.08, 0.6)),
+                materials.add(Color::srgb_u8(170, 0, 255)),
+                position,
+                Arrow {
+                    speed: 20.0,
+                    range: 30.0,
+                    direction,
+                    distance_travelled: 0.0,
+                    strength: 1.0,
+                    pierce,
+                    hit_enemies: Vec::new(),
+                },
+            );
+            shot_fired_events.send(ShotFired {
+                tier: ChargeTier::Medium,
+                ability: Some(ability),
+            });
+        }
+    }
+}
+
+fn tick_ability_cooldowns(mut abilities: Query<&mut Abilities>, time: Res<Time>) {
+    for mut abilities in abilities.iter_mut() {
+        for ability in abilities.0.iter_mut() {
+            ability.tick(time.delta());
+        }
+    }
+}
+
+fn player_shoot(
+    mut commands: Commands,
+    mut player: Query<(&Transform, &mut Player, &mut Abilities), Without<Camera>>,
+    camera: Query<(&Transform, &Camera, &ThirdPersonCamera), Without<Player>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<Settings>,
+    mut shot_fired_events: EventWriter<ShotFired>,
+) {
+    let (player_transform, mut player, mut abilities) = player.single_mut();
+    let (camera_transform, _, _) = camera.single();
+    let (spawn_position, direction) =
+        shot_origin(player_transform, &player, camera_transform, &settings);
+
+    if !player.is_charging && player.charge_timer.elapsed_secs() > 0.0 {
+        // Calculate strength based on charge time
+        let charge_time = player
+            .charge_timer
+            .elapsed_secs()
+            .min(player.max_charge_duration);
+        let strength = charge_time / player.max_charge_duration;
+        let tier = ChargeTier::from_strength(strength);
+
+        player.charge_timer.reset();
         player.shoot_cooldown.reset();
 
-        if strength >= 0.66 {
-            power_shot_cooldown_timer.0.reset();
+        // A shot charged into the power tier triggers the charged-release ability instead
+        if tier == ChargeTier::Power {
+            if let Some(ability) = abilities
+                .0
+                .iter_mut()
+                .find(|ability| ability.activation == AbilityActivation::ChargedRelease)
+            {
+                if ability.try_use() {
+                    fire_ability_effect(
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                        &mut shot_fired_events,
+                        ability.id,
+                        ability.effect,
+                        spawn_position,
+                        direction,
+                        strength,
+                    );
+                    return;
+                }
+            }
+        }
+
+        let (projectile_color, projectile_mesh) = projectile_appearance(tier, &mut meshes);
+        spawn_arrow(
+            &mut commands,
+            projectile_mesh,
+            materials.add(projectile_color),
+            spawn_position,
+            Arrow::charged(direction, strength),
+        );
+        shot_fired_events.send(ShotFired {
+            tier,
+            ability: None,
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn activate_abilities(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut player: Query<(&Transform, &Player, &mut Abilities), Without<Camera>>,
+    camera: Query<&Transform, (With<ThirdPersonCamera>, Without<Player>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<Settings>,
+    mut shot_fired_events: EventWriter<ShotFired>,
+) {
+    let (player_transform, player, mut abilities) = player.single_mut();
+    let (spawn_position, direction) =
+        shot_origin(player_transform, player, camera.single(), &settings);
+
+    for ability in abilities.0.iter_mut() {
+        let AbilityActivation::Key(key) = ability.activation else {
+            continue;
+        };
+
+        if keyboard_input.just_pressed(key) && ability.try_use() {
+            fire_ability_effect(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut shot_fired_events,
+                ability.id,
+                ability.effect,
+                spawn_position,
+                direction,
+                1.0,
+            );
         }
     }
 }
 
 fn arrow_movement(
     mut commands: Commands,
-    mut arrows: Query<(Entity, &mut Transform, &mut Arrow)>,
+    mut arrows: Query<(Entity, &mut Transform, &mut Arrow, Option<&Volley>)>,
     time: Res<Time>,
+    mut arrow_missed_events: EventWriter<ArrowMissed>,
 ) {
-    for (entity, mut transform, mut arrow) in arrows.iter_mut() {
-        transform.translation += arrow.direction * arrow.speed * time.delta_seconds();
-        arrow.distance_travelled += arrow.speed * time.delta_seconds();
+    for (entity, mut transform, mut arrow, volley) in arrows.iter_mut() {
+        transform.translation = arrow.step(transform.translation, time.delta_seconds());
 
-        if arrow.distance_travelled >= arrow.range {
+        if arrow.is_finished(transform.translation) {
             commands.entity(entity).despawn();
+            // Piercing arrows that hit something before running out are not a miss, and neither
+            // are the arrows of a volley that hit with another arrow
+            if arrow.hit_enemies.is_empty() && !volley.is_some_and(|volley| volley.hit) {
+                arrow_missed_events.send(ArrowMissed);
+            }
         }
     }
 }
 
-fn update_power_cooldown_ui(
-    mut cooldown_ui: Query<(&mut Visibility, &PowerShotCooldownUI)>,
-    mut cooldown_text: Query<(&mut Text, &PowerShotCooldownUI)>,
-    power_shot_cooldown_timer: ResMut<PowerShotCooldownTimer>,
+// Hotbar layout, in the reference resolution
+const HOTBAR_SLOT_SIZE: f32 = 100.0;
+const HOTBAR_SLOT_SPACING: f32 = 20.0;
+const HOTBAR_BOTTOM: f32 = 25.0;
+const HOTBAR_PIP_SIZE: f32 = 12.0;
+
+// Build a hotbar slot for every ability whenever a player with abilities is spawned
+fn spawn_ability_hotbar(
+    mut commands: Commands,
+    abilities: Query<&Abilities, Added<Abilities>>,
+    asset_server: Res<AssetServer>,
+    mut cooldown_ui_materials: ResMut<Assets<CooldownUiMaterial>>,
 ) {
-    let cooldown_timer = &power_shot_cooldown_timer.0;
+    for abilities in abilities.iter() {
+        let slot_count = abilities.0.len() as f32;
+        let hotbar_width = slot_count * HOTBAR_SLOT_SIZE + (slot_count - 1.0) * HOTBAR_SLOT_SPACING;
+        let hotbar_left = (MAX_WIDTH - hotbar_width) / 2.0;
+        let slot_size = Vec2::splat(HOTBAR_SLOT_SIZE);
+
+        // The slots share one root so they keep their spacing at any UI scale
+        let hotbar = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Ui2DComponent::new(
+                    Vec2::new(hotbar_left, HOTBAR_BOTTOM),
+                    Vec2::new(hotbar_width, HOTBAR_SLOT_SIZE),
+                ),
+            ))
+            .id();
+
+        for (index, ability) in abilities.0.iter().enumerate() {
+            let slot_position =
+                Vec2::new(index as f32 * (HOTBAR_SLOT_SIZE + HOTBAR_SLOT_SPACING), 0.0);
+            let (image, background_color) = match ability.id.icon() {
+                Some(path) => (
+                    UiImage {
+                        texture: asset_server.load(path),
+                        ..Default::default()
+                    },
+                    BackgroundColor(Color::WHITE),
+                ),
+                None => (
+                    UiImage::default(),
+                    BackgroundColor(ability.id.color().with_alpha(0.8)),
+                ),
+            };
+
+            commands
+                .spawn((
+                    ImageBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        image,
+                        background_color,
+                        ..Default::default()
+                    },
+                    BorderRadius::all(Val::Percent(50.0)),
+                    Ui2DComponent::new(slot_position, slot_size),
+                    AbilitySlot {
+                        ability: ability.id,
+                        last_charges: ability.charges,
+                    },
+                ))
+                .set_parent(hotbar)
+                .with_children(|parent| {
+                    // Initial for abilities without an icon
+                    if ability.id.icon().is_none() {
+                        parent.spawn(TextBundle::from_section(
+                            ability.id.initial(),
+                            TextStyle {
+                                font_size: 48.0,
+                                color: Color::WHITE,
+                                ..Default::default()
+                            },
+                        ));
+                    }
 
-    if cooldown_ui.iter().count() == 0 {
-        return;
+                    // Overlay shown while the ability has no charges left
+                    parent.spawn((
+                        MaterialNodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            material: cooldown_ui_materials.add(CooldownUiMaterial {
+                                color: [0.15, 0.15, 0.15, 1.].into(),
+                                rim_color: [0.05, 0.05, 0.05, 1.].into(),
+                                glow_color: [1.0, 0.85, 0.4, 0.8].into(),
+                                progress: 0.0,
+                                rim_width: 0.08,
+                                glow_width: 0.1,
+                            }),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                        Ui2DComponent::new(Vec2::ZERO, slot_size),
+                        AbilityCooldownOverlay {
+                            ability: ability.id,
+                        },
+                    ));
+
+                    // White disc that fades out when a charge comes back
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            background_color: BackgroundColor(Color::NONE),
+                            border_radius: BorderRadius::all(Val::Percent(50.0)),
+                            ..default()
+                        },
+                        Ui2DComponent::new(Vec2::ZERO, slot_size),
+                        AbilityReadyFlash {
+                            ability: ability.id,
+                            timer: Timer::from_seconds(0.0, TimerMode::Once),
+                        },
+                    ));
+
+                    // Countdown until the next charge
+                    parent.spawn((
+                        TextBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            text: Text::from_section("", TextStyle::default())
+                                .with_justify(JustifyText::Center),
+                            visibility: Visibility::Hidden,
+                            ..Default::default()
+                        },
+                        Ui2DComponent::new(Vec2::new(0.0, 30.0), Vec2::new(100.0, 40.0)),
+                        Ui2DText { font_size: 30.0 },
+                        AbilityCooldownText {
+                            ability: ability.id,
+                        },
+                    ));
+
+                    // Keybind label below the slot
+                    parent.spawn((
+                        TextBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                ability.activation.label(),
+                                TextStyle::default(),
+                            )
+                            .with_justify(JustifyText::Center),
+                            ..Default::default()
+                        },
+                        Ui2DComponent::new(Vec2::new(0.0, -22.0), Vec2::new(100.0, 20.0)),
+                        Ui2DText { font_size: 18.0 },
+                    ));
+
+                    // One pip per charge along the top of the slot
+                    if ability.max_charges > 1 {
+                        let pips_width = ability.max_charges as f32 * HOTBAR_PIP_SIZE * 1.5
+                            - HOTBAR_PIP_SIZE * 0.5;
+                        for pip in 0..ability.max_charges {
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        ..default()
+                                    },
+                                    border_radius: BorderRadius::all(Val::Percent(50.0)),
+                                    ..default()
+                                },
+                                Ui2DComponent::new(
+                                    Vec2::new(
+                                        (HOTBAR_SLOT_SIZE - pips_width) / 2.0
+                                            + pip as f32 * HOTBAR_PIP_SIZE * 1.5,
+                                        HOTBAR_SLOT_SIZE + 4.0,
+                                    ),
+                                    Vec2::splat(HOTBAR_PIP_SIZE),
+                                ),
+                                AbilityChargePip {
+                                    ability: ability.id,
+                                    index: pip,
+                                },
+                            ));
+                        }
+                    }
+                });
+        }
     }
+}
 
-    for (mut visibility, _) in cooldown_ui.iter_mut() {
-        if cooldown_timer.remaining_secs() > 0.0 {
-            *visibility = Visibility::Visible;
-        } else {
-            *visibility = Visibility::Visible;
+#[allow(clippy::too_many_arguments)]
+fn update_ability_hotbar(
+    abilities: Query<&Abilities, With<Player>>,
+    mut slots: Query<&mut AbilitySlot>,
+    mut overlays: Query<
+        (
+            &mut Visibility,
+            &Handle<CooldownUiMaterial>,
+            &AbilityCooldownOverlay,
+        ),
+        Without<AbilityCooldownText>,
+    >,
+    mut cooldown_ui_materials: ResMut<Assets<CooldownUiMaterial>>,
+    mut cooldown_text: Query<(&mut Text, &mut Visibility, &AbilityCooldownText)>,
+    mut pips: Query<(&mut BackgroundColor, &AbilityChargePip), Without<AbilityReadyFlash>>,
+    mut flashes: Query<(&mut BackgroundColor, &mut AbilityReadyFlash), Without<AbilityChargePip>>,
+    time: Res<Time>,
+) {
+    let abilities = abilities.single();
+
+    // Flash the slots whose ability just got a charge back
+    let mut restored = Vec::new();
+    for mut slot in slots.iter_mut() {
+        let Some(ability) = abilities.get(slot.ability) else {
+            continue;
+        };
+        if ability.charges > slot.last_charges {
+            restored.push(slot.ability);
         }
+        slot.last_charges = ability.charges;
     }
 
-    if power_shot_cooldown_timer.0.remaining_secs() > 0.0 {
-        let (mut text, _) = cooldown_text.single_mut();
-        text.sections[0].value = format!("{:.0}", power_shot_cooldown_timer.0.remaining_secs());
-    } else {
-        for (mut visibility, _) in cooldown_ui.iter_mut() {
+    // Sweep the overlay away as the next charge comes back, darker while no charge is left
+    for (mut visibility, material, overlay) in overlays.iter_mut() {
+        let Some(ability) = abilities.get(overlay.ability) else {
+            continue;
+        };
+        if !ability.is_recharging() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Inherited;
+        if let Some(material) = cooldown_ui_materials.get_mut(material) {
+            material.progress = ability.remaining_fraction();
+            material.color.w = if ability.is_ready() { 0.5 } else { 1.0 };
+        }
+    }
+
+    for (mut text, mut visibility, cooldown) in cooldown_text.iter_mut() {
+        let Some(ability) = abilities.get(cooldown.ability) else {
+            continue;
+        };
+        if ability.is_recharging() {
+            text.sections[0].value = format!("{:.0}", ability.remaining_secs().ceil());
+            *visibility = Visibility::Inherited;
+        } else {
             *visibility = Visibility::Hidden;
         }
     }
+
+    for (mut background_color, pip) in pips.iter_mut() {
+        let Some(ability) = abilities.get(pip.ability) else {
+            continue;
+        };
+        *background_color = if pip.index < ability.charges {
+            BackgroundColor(Color::srgb(1.0, 0.85, 0.2))
+        } else {
+            BackgroundColor(Color::srgba(0.2, 0.2, 0.2, 0.8))
+        };
+    }
+
+    for (mut background_color, mut flash) in flashes.iter_mut() {
+        if restored.contains(&flash.ability) {
+            flash.timer = Timer::from_seconds(0.4, TimerMode::Once);
+        }
+        flash.timer.tick(time.delta());
+        *background_color = BackgroundColor(Color::srgba(
+            1.0,
+            1.0,
+            1.0,
+            0.7 * (1.0 - flash.timer.fraction()),
+        ));
+    }
 }
 
 fn update_ui_2d(
-    mut ui_2d_icons: Query<(&mut Style, &Ui2DComponent), Without<Ui2DText>>,
-    mut ui_2d_text: Query<(&mut Style, &mut Text, &Ui2DComponent, &Ui2DText)>,
+    mut ui_2d_icons: Query<(&mut Style, &Ui2DComponent, Has<Parent>), Without<Ui2DText>>,
+    mut ui_2d_text: Query<(
+        &mut Style,
+        &mut Text,
+        &Ui2DComponent,
+        &Ui2DText,
+        Has<Parent>,
+    )>,
     window: Query<&Window>,
+    settings: Res<Settings>,
 ) {
     let mut current_window_width = MIN_WIDTH;
     let mut current_window_height = MIN_HEIGHT;
+    let ui_scale = settings.accessibility.ui_scale;
 
     for window in window.iter() {
         current_window_width = window.width();
         current_window_height = window.height();
     }
 
+    // Children are offset inside their parent and scale exactly like its size to stay in place.
+    // Top level elements grow away from the closest screen edge instead: the point as far
+    // across the element as the element is across the screen stays where it was
+    let screen_ratio = Vec2::new(
+        current_window_width / MAX_WIDTH,
+        current_window_height / MAX_HEIGHT,
+    );
+    let size_ratio = current_window_height / MAX_HEIGHT * ui_scale;
+    let position = |ui_component: &Ui2DComponent, is_child: bool| {
+        if is_child {
+            return ui_component.base_position * size_ratio;
+        }
+        let room = (Vec2::new(MAX_WIDTH, MAX_HEIGHT) - ui_component.base_size).max(Vec2::ONE);
+        let anchor = (ui_component.base_position / room).clamp(Vec2::ZERO, Vec2::ONE);
+        (ui_component.base_position + ui_component.base_size * anchor) * screen_ratio
+            - ui_component.base_size * size_ratio * anchor
+    };
+
     // Loop through all UI components
-    for (mut style, ui_component) in ui_2d_icons.iter_mut() {
+    for (mut style, ui_component, is_child) in ui_2d_icons.iter_mut() {
         // Adjust position and size using the scaling factors
-        style.left = Val::Px((current_window_width / MAX_WIDTH) * ui_component.base_position.x);
-        style.bottom = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_position.y);
-        style.width = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.x);
-        style.height = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.y);
+        let position = position(ui_component, is_child);
+        style.left = Val::Px(position.x);
+        style.bottom = Val::Px(position.y);
+        style.width =
+            Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.x * ui_scale);
+        style.height =
+            Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.y * ui_scale);
     }
 
     // Loop through all UI text components
-    for (mut style, mut text, ui_component, ui_text) in ui_2d_text.iter_mut() {
+    for (mut style, mut text, ui_component, ui_text, is_child) in ui_2d_text.iter_mut() {
         // Adjust position and size using the scaling factors
-        style.left = Val::Px((current_window_width / MAX_WIDTH) * ui_component.base_position.x);
-        style.bottom = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_position.y);
-        style.width = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.x);
-        style.height = Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.y);
+        let position = position(ui_component, is_child);
+        style.left = Val::Px(position.x);
+        style.bottom = Val::Px(position.y);
+        style.width =
+            Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.x * ui_scale);
+        style.height =
+            Val::Px((current_window_height / MAX_HEIGHT) * ui_component.base_size.y * ui_scale);
 
         // Adjust font size using the scaling factor
-        text.sections[0].style.font_size = ui_text.font_size * (current_window_height / MAX_HEIGHT);
+        text.sections[0].style.font_size =
+            ui_text.font_size * (current_window_height / MAX_HEIGHT) * ui_scale;
     }
 }
 
@@ -590,11 +1671,13 @@ fn random_spawn_enemies(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
     time: Res<Time>,
+    settings: Res<Settings>,
+    mut run_rng: ResMut<RunRng>,
 ) {
     let (player_transform, _) = player.single();
     let player_translation = player_transform.translation;
-    let mut rng = rand::thread_rng();
-    let spawn_interval = 2.0;
+    let rng = &mut run_rng.0;
+    let spawn_interval = settings.gameplay.enemy_spawn_interval;
     let spawn_chance = 0.5;
 
     if time.elapsed_seconds() % spawn_interval < time.delta_seconds() {
@@ -619,218 +1702,414 @@ fn random_spawn_enemies(
                     transform: Transform::from_translation(enemy_position),
                     ..Default::default()
                 },
-                Enemy {},
+                Enemy {
+                    health: Enemy::MAX_HEALTH,
+                },
+                CameraObstacle {
+                    radius: Enemy::HALF_SIZE.length(),
+                },
             ));
         }
     }
 }
 
-fn hit_collision(
-    mut commands: Commands,
-    mut arrows: Query<(Entity, &Transform, &Arrow)>,
-    mut enemies: Query<(Entity, &Transform), With<Enemy>>,
-    mut player: Query<(&mut Player, &Transform)>,
-    mut next_game_state: ResMut<NextState<GameState>>,
-    mut game_over_text: Query<(&mut Visibility, &GameOverText)>,
-) {
-    let (mut player, player_transform) = player.single_mut();
-    for (arrow_entity, arrow_transform, _) in arrows.iter_mut() {
-        for (enemy_entity, enemy_transform) in enemies.iter_mut() {
-            // Distance for collision could be adjusted
-            if arrow_transform
-                .translation
-                .distance(enemy_transform.translation)
-                < 1.0
-            {
-                player.score += 1;
-                commands.entity(arrow_entity).despawn();
-                commands.entity(enemy_entity).despawn();
-            }
-        }
+// How far outside an enemy's bounds an arrow still counts as a hit
+const HIT_TOLERANCE: f32 = 0.75;
+// Hits on the top part of an enemy, above this fraction of its half height, are critical
+const CRITICAL_HEIGHT: f32 = 0.5;
+const CRITICAL_DAMAGE_MULTIPLIER: f32 = 2.0;
+
+// Find where an arrow at `point` flying along `direction` would strike the enemy's box, if it
+// is close enough to hit. Only arrows whose line really passes through the head are critical,
+// near misses within the tolerance still hit but never crit
+fn hit_location(enemy_transform: &Transform, point: Vec3, direction: Vec3) -> Option<(Vec3, bool)> {
+    let inverse_rotation = enemy_transform.rotation.inverse();
+    let local_point = inverse_rotation * (point - enemy_transform.translation);
+    let closest = local_point.clamp(-Enemy::HALF_SIZE, Enemy::HALF_SIZE);
+
+    if local_point.distance(closest) > HIT_TOLERANCE {
+        return None;
     }
 
-    for (_, enemy_transform) in enemies.iter_mut() {
-        // Distance for collision could be adjusted
-        if player_transform
-            .translation
-            .distance(enemy_transform.translation)
-            < 1.0
-        {
-            next_game_state.set(GameState::GameOver);
-            let (mut visibility, _) = game_over_text.single_mut();
-            *visibility = Visibility::Visible;
-            break;
+    let (strike, critical) = match ray_box_entry(local_point, inverse_rotation * direction) {
+        Some(entry) => (entry, entry.y >= Enemy::HALF_SIZE.y * CRITICAL_HEIGHT),
+        None => (closest, false),
+    };
+    Some((
+        enemy_transform.translation + enemy_transform.rotation * strike,
+        critical,
+    ))
+}
+
+// Where the line through `origin` along `direction` enters an enemy-sized box centered on the
+// origin of its space, if it passes through it at all
+fn ray_box_entry(origin: Vec3, direction: Vec3) -> Option<Vec3> {
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    for axis in 0..3 {
+        let (position, speed, half) = (origin[axis], direction[axis], Enemy::HALF_SIZE[axis]);
+        if speed.abs() < f32::EPSILON {
+            if position.abs() > half {
+                return None;
+            }
+            continue;
         }
+        let (a, b) = ((-half - position) / speed, (half - position) / speed);
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
     }
-}
 
-fn setup_player_score(mut commands: Commands) {
-    commands.spawn((
-        TextBundle {
-            text: Text {
-                sections: vec![TextSection {
-                    value: "Score: 0".into(),
-                    style: TextStyle {
-                        font: Default::default(),
-                        font_size: 40.0,
-                        color: Color::WHITE,
-                    },
-                }],
-                ..Default::default()
-            },
-            style: Style {
-                position_type: PositionType::Absolute,
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-        Ui2DComponent::new(Vec2::new(20.0, 680.0), Vec2::new(200.0, 100.0)),
-        Ui2DText { font_size: 40.0 },
-        Score {},
-    ));
+    (enter <= exit).then(|| origin + direction * enter)
 }
 
-fn update_player_score(player: Query<&Player>, mut text: Query<(&mut Text, &Score)>) {
-    let player = player.single();
-    let (mut text, _) = text.single_mut();
-    text.sections[0].value = format!("Score: {}", player.score);
+// The points an arrow at `position` passes this frame, spaced like the trajectory preview
+// traces them from the shot origin, so a fast arrow can't skip over an enemy the preview
+// shows it hitting
+fn arrow_path(position: Vec3, arrow: &Arrow, delta: f32) -> Vec<Vec3> {
+    let end = arrow.distance_travelled + arrow.speed * delta;
+    let mut distance =
+        ((arrow.distance_travelled / TRAJECTORY_STEP).floor() + 1.0) * TRAJECTORY_STEP;
+    let mut points = Vec::new();
+    while distance <= end {
+        let point = position + arrow.direction * (distance - arrow.distance_travelled);
+        points.push(point);
+        // The preview also checks the point where the arrow runs out before stopping
+        if distance >= arrow.range || point.y <= Arrow::GROUND_HEIGHT {
+            break;
+        }
+        distance += TRAJECTORY_STEP;
+    }
+    points
 }
 
-fn enemies_walking(
-    mut enemies: Query<(&mut Transform, &Enemy), Without<Player>>,
-    player_query: Query<(&Transform, &Player), Without<Enemy>>,
+// Runs before arrow_movement and checks the stretch each arrow is about to fly this frame
+fn hit_collision(
+    mut commands: Commands,
+    mut arrows: Query<(Entity, &Transform, &mut Arrow, Option<&mut Volley>)>,
+    mut enemies: Query<(Entity, &Transform, &mut Enemy, &Handle<StandardMaterial>)>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut arrow_hit_events: EventWriter<ArrowHit>,
+    mut spawn_particles_events: EventWriter<SpawnParticles>,
+    time: Res<Time>,
 ) {
-    // Get the player's position (assuming there is only one player)
-    let (player_transform, _) = player_query.single();
-
-    for (mut transform, _) in enemies.iter_mut() {
-        // Calculate the direction from the enemy to the player
-        let direction_to_player = player_transform.translation - transform.translation;
-
-        // Normalize the direction to get a unit vector
-        let direction = direction_to_player.normalize();
-
-        // Move the enemy towards the player
-        transform.translation += direction * 0.01; // Adjust the speed as necessary
+    let mut hit_volleys = HashSet::new();
+    for (arrow_entity, arrow_transform, mut arrow, volley) in arrows.iter_mut() {
+        let path = arrow_path(arrow_transform.translation, &arrow, time.delta_seconds());
+        let mut spent = false;
+        for (enemy_entity, enemy_transform, mut enemy, material) in enemies.iter_mut() {
+            // Enemies killed earlier this frame only stop being enemies at the end of it
+            if enemy.health <= 0.0 || arrow.hit_enemies.contains(&enemy_entity) {
+                continue;
+            }
 
-        // Calculate the new rotation for the enemy to face the player
-        // Assumes 2D movement on the XZ plane; you can adjust if using 3D
-        let target_rotation = Quat::from_rotation_arc(Vec3::Z, direction);
+            if let Some((position, critical)) = path
+                .iter()
+                .find_map(|&point| hit_location(enemy_transform, point, arrow.direction))
+            {
+                let damage = if critical {
+                    arrow.damage() * CRITICAL_DAMAGE_MULTIPLIER
+                } else {
+                    arrow.damage()
+                };
+                enemy.health -= damage;
+                let killed = enemy.health <= 0.0;
+
+                // Piercing arrows keep flying until they have gone through enough enemies
+                arrow.hit_enemies.push(enemy_entity);
+                if !spent && arrow.hit_enemies.len() as u32 > arrow.pierce {
+                    commands.entity(arrow_entity).despawn();
+                    spent = true;
+                }
+                if killed {
+                    commands
+                        .entity(enemy_entity)
+                        .remove::<(Enemy, EnemyWarning, CameraObstacle)>()
+                        .insert(Dying::new(*enemy_transform, arrow.direction));
+
+                    // Shatter into small cubes of the enemy's color
+                    let color = materials
+                        .get(material)
+                        .map_or(Color::WHITE, |material| material.base_color);
+                    spawn_particles_events.send(SpawnParticles {
+                        emitter: ParticleEmitter::shatter(color),
+                        position: enemy_transform.translation,
+                        direction: Vec3::Y,
+                    });
+                }
+
+                let tier = ChargeTier::from_strength(arrow.strength);
+                spawn_particles_events.send(SpawnParticles {
+                    emitter: ParticleEmitter::impact(tier.color()),
+                    position,
+                    direction: -arrow.direction,
+                });
+                arrow_hit_events.send(ArrowHit {
+                    arrow: arrow_entity,
+                    position,
+                    tier,
+                    damage,
+                    critical,
+                    killed,
+                });
+                if let Some(volley) = &volley {
+                    hit_volleys.insert(volley.id);
+                }
+            }
+        }
+    }
 
-        // Set the enemy's rotation
-        transform.rotation = target_rotation;
+    // The rest of a volley that hit no longer counts as a miss
+    for (_, _, _, volley) in arrows.iter_mut() {
+        if let Some(mut volley) = volley {
+            if hit_volleys.contains(&volley.id) {
+                volley.hit = true;
+            }
+        }
     }
 }
 
-fn check_enemy_proximity(
+// Tip killed enemies over while they shrink and fade out, then despawn them
+fn animate_dying_enemies(
     mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
-    warning_query: Query<(Entity, &WarningSign)>,
+    mut dying: Query<(
+        Entity,
+        &mut Dying,
+        &mut Transform,
+        &Handle<StandardMaterial>,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
 ) {
-    let player_transform = player_query.single();
+    for (entity, mut dying, mut transform, material) in dying.iter_mut() {
+        dying.timer.tick(time.delta());
+        if dying.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
 
-    for (enemy_entity, enemy_transform) in enemy_query.iter() {
-        let distance = player_transform
+        // Falls quickly at first and settles, shrinking towards the ground
+        let t = dying.timer.fraction();
+        let fall = 1.0 - (1.0 - t) * (1.0 - t);
+        let scale = 1.0 - 0.6 * t;
+        transform.rotation =
+            Quat::from_axis_angle(dying.fall_axis, FRAC_PI_2 * fall) * dying.start.rotation;
+        transform.scale = dying.start.scale * scale;
+        transform.translation = dying
+            .start
             .translation
-            .distance(enemy_transform.translation);
-
-        // Check if the enemy is within the danger zone
-        if distance < 10.0 {
-            // Check if a warning sign for this enemy already exists
-            let existing_warning = warning_query
-                .iter()
-                .any(|(_, warning)| warning.enemy_entity == enemy_entity);
+            .with_y(dying.start.translation.y * scale);
 
-            if !existing_warning {
-                // Spawn a warning sign and link it to the enemy
-                spawn_warning_exclamation(
-                    &mut commands,
-                    player_transform,
-                    enemy_transform,
-                    enemy_entity,
-                );
-            }
+        if let Some(material) = materials.get_mut(material) {
+            material.alpha_mode = AlphaMode::Blend;
+            material.base_color.set_alpha(1.0 - t);
         }
     }
 }
 
-fn spawn_warning_exclamation(
-    commands: &mut Commands,
-    player_transform: &Transform,
-    enemy_transform: &Transform,
-    enemy_entity: Entity,
+// Contact damage tuning
+const ENEMY_CONTACT_DISTANCE: f32 = 1.0;
+const ENEMY_CONTACT_DAMAGE: f32 = 25.0;
+const KNOCKBACK_SPEED: f32 = 6.0;
+const KNOCKBACK_DECAY: f32 = 8.0; // Per second, higher stops the knockback sooner
+const HEALTH_REGEN_DELAY: f32 = 4.0;
+const HEALTH_REGEN_PER_SEC: f32 = 5.0;
+
+// Enemies touching the player deal damage and knock them back, or end the run in hardcore mode
+fn enemy_contact_damage(
+    mut player: Query<(&Transform, &mut Health, &mut Knockback), With<Player>>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    run_info: Res<RunInfo>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut game_over_text: Query<&mut Visibility, With<GameOverText>>,
+    mut player_damaged_events: EventWriter<PlayerDamaged>,
 ) {
-    let direction = enemy_transform.translation - player_transform.translation;
-    let direction_normalized = direction.normalize();
+    let (player_transform, mut health, mut knockback) = player.single_mut();
 
-    let screen_position = calculate_ui_position(direction_normalized);
+    for enemy_transform in enemies.iter() {
+        // Distance for collision could be adjusted
+        if player_transform
+            .translation
+            .distance(enemy_transform.translation)
+            >= ENEMY_CONTACT_DISTANCE
+        {
+            continue;
+        }
 
-    // Spawn the warning sign linked to this enemy
-    commands.spawn((
-        WarningSignBundle::new(screen_position),
-        WarningSign {
-            enemy_entity, // Link this warning to the enemy
-        },
-        WarningSignAnimation {},
-        Ui2DComponent::new(screen_position, Vec2::new(50.0, 50.0)),
-    ));
-}
-
-fn calculate_ui_position(direction: Vec3) -> Vec2 {
-    // Convert the 3D direction into a 2D screen-space position
-    let x = direction.x * 100.0; // Scale factor to control how far on the screen
-    let y = direction.z * 100.0; // You can also use y here for vertical movement
+        // Hardcore contact is fatal, but a dodge still avoids it
+        let amount = if run_info.mode == GameMode::Hardcore {
+            health.current
+        } else {
+            ENEMY_CONTACT_DAMAGE
+        };
+        if health.damage(amount) {
+            player_damaged_events.send(PlayerDamaged { amount });
+            let away = (player_transform.translation - enemy_transform.translation)
+                .with_y(0.0)
+                .normalize_or_zero();
+            knockback.velocity = away * KNOCKBACK_SPEED;
+        }
 
-    Vec2::new(x, y)
+        if health.is_dead() {
+            next_game_state.set(GameState::GameOver);
+            *game_over_text.single_mut() = Visibility::Visible;
+        }
+        break;
+    }
 }
 
-fn update_warning_positions(
-    player_query: Query<&Transform, With<Player>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
-    mut warning_query: Query<(&mut Style, &WarningSign)>,
+// Tick invulnerability, regenerate health and move the player along their knockback
+fn update_player_health(
+    mut player: Query<(&mut Transform, &mut Health, &mut Knockback), With<Player>>,
+    run_info: Res<RunInfo>,
+    settings: Res<Settings>,
+    time: Res<Time>,
 ) {
-    let player_transform = player_query.single();
+    let (mut transform, mut health, mut knockback) = player.single_mut();
+
+    health.invulnerability.tick(time.delta());
+    health.since_damage.tick(time.delta());
+    if settings.gameplay.health_regen
+        && run_info.mode != GameMode::Hardcore
+        && health.since_damage.elapsed_secs() >= HEALTH_REGEN_DELAY
+    {
+        health.heal(HEALTH_REGEN_PER_SEC * time.delta_seconds());
+    }
 
-    for (mut style, warning_sign) in warning_query.iter_mut() {
-        if let Ok((_, enemy_transform)) = enemy_query.get(warning_sign.enemy_entity) {
-            let direction = enemy_transform.translation - player_transform.translation;
-            let direction_normalized = direction.normalize();
+    transform.translation += knockback.velocity * time.delta_seconds();
+    knockback.velocity *= (-KNOCKBACK_DECAY * time.delta_seconds()).exp();
+}
 
-            let screen_position = calculate_ui_position(direction_normalized);
+// Blink the player's material while they are invulnerable after a hit
+fn flash_invulnerable_player(
+    player: Query<(&Health, &Handle<StandardMaterial>), With<Player>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (health, material_handle) = player.single();
+    if !health.is_recovering() {
+        return;
+    }
 
-            // Update the position of the warning sign
-            style.left = Val::Px(screen_position.x);
-            style.top = Val::Px(screen_position.y);
+    if let Some(material) = materials.get_mut(material_handle) {
+        let blink = (health.invulnerability.elapsed_secs() * 5.0).fract() < 0.5;
+        if blink {
+            material.base_color = Color::srgb(1.0, 0.2, 0.2);
         }
     }
 }
 
-fn remove_far_warning_signs(
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
-    mut warning_query: Query<(Entity, &WarningSign)>,
-    mut commands: Commands,
-) {
-    for (warning_entity, warning_sign) in warning_query.iter_mut() {
-        if let Err(_) = enemy_query.get(warning_sign.enemy_entity) {
-            commands.entity(warning_entity).despawn();
-        }
-    }
+// Health bar size, in the reference resolution
+const HEALTH_BAR_SIZE: Vec2 = Vec2::new(300.0, 18.0);
+const STAMINA_BAR_SIZE: Vec2 = Vec2::new(300.0, 8.0);
+
+fn setup_health_bar(mut commands: Commands) {
+    let health_bar = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                border_radius: BorderRadius::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            Ui2DComponent::new(Vec2::new(20.0, 655.0), HEALTH_BAR_SIZE),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.2, 0.8, 0.2)),
+                    border_radius: BorderRadius::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                Ui2DComponent::new(Vec2::ZERO, HEALTH_BAR_SIZE),
+                HealthBarFill {},
+            ));
+
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    text: Text::from_section("", TextStyle::default())
+                        .with_justify(JustifyText::Center),
+                    ..Default::default()
+                },
+                Ui2DComponent::new(Vec2::ZERO, HEALTH_BAR_SIZE),
+                Ui2DText { font_size: 16.0 },
+                HealthBarText {},
+            ));
+        })
+        .id();
+
+    // Stamina bar right under the health bar, as its child so the gap scales with it
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                border_radius: BorderRadius::all(Val::Px(3.0)),
+                ..Default::default()
+            },
+            Ui2DComponent::new(Vec2::new(0.0, -14.0), STAMINA_BAR_SIZE),
+        ))
+        .set_parent(health_bar)
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.95, 0.8, 0.2)),
+                    border_radius: BorderRadius::all(Val::Px(3.0)),
+                    ..Default::default()
+                },
+                Ui2DComponent::new(Vec2::ZERO, STAMINA_BAR_SIZE),
+                StaminaBarFill {},
+            ));
+        });
 }
 
-fn game_over(
-    mut commands: Commands,
-    curr_game_state: Res<State<GameState>>,
-    mut next_game_state: ResMut<NextState<GameState>>,
+fn update_health_bar(
+    player: Query<(&Health, &Stamina), With<Player>>,
+    mut fill: Query<(&mut Ui2DComponent, &mut BackgroundColor), With<HealthBarFill>>,
+    mut stamina_fill: Query<&mut Ui2DComponent, (With<StaminaBarFill>, Without<HealthBarFill>)>,
+    mut text: Query<&mut Text, With<HealthBarText>>,
+    run_info: Res<RunInfo>,
 ) {
+    let (health, stamina) = player.single();
+    stamina_fill.single_mut().base_size.x = STAMINA_BAR_SIZE.x * stamina.fraction();
+    let (mut fill_component, mut fill_color) = fill.single_mut();
+
+    // Green when healthy, red when about to die
+    fill_component.base_size.x = HEALTH_BAR_SIZE.x * health.fraction();
+    fill_color.0 = Color::srgb(0.9, 0.15, 0.15).mix(&Color::srgb(0.2, 0.8, 0.2), health.fraction());
+
+    text.single_mut().sections[0].value = if run_info.mode == GameMode::Hardcore {
+        "Hardcore".to_string()
+    } else {
+        format!("{:.0} / {:.0}", health.current.ceil(), health.max)
+    };
+}
+
+fn setup_player_score(mut commands: Commands) {
     commands.spawn((
         TextBundle {
             text: Text {
                 sections: vec![TextSection {
-                    value: "Game Over!\nPress R to restart".to_string(),
+                    value: "Score: 0".into(),
                     style: TextStyle {
                         font: Default::default(),
-                        font_size: 75.0,
+                        font_size: 40.0,
                         color: Color::WHITE,
                     },
                 }],
@@ -840,51 +2119,1631 @@ fn game_over(
                 position_type: PositionType::Absolute,
                 ..Default::default()
             },
-            visibility: Visibility::Hidden,
             ..Default::default()
         },
-        Ui2DComponent::new(
-            Vec2::new(
-                MAX_WIDTH / 4.0 + MAX_WIDTH / 32.0,
-                MAX_HEIGHT / 4.0 + MAX_HEIGHT / 16.0,
-            ),
-            Vec2::new(MAX_WIDTH / 2.0, MAX_HEIGHT / 2.0),
-        ),
-        Ui2DText { font_size: 75.0 },
-        GameOverText {},
+        Ui2DComponent::new(Vec2::new(20.0, 680.0), Vec2::new(400.0, 100.0)),
+        Ui2DText { font_size: 40.0 },
+        Score {
+            pulse: Timer::from_seconds(0.0, TimerMode::Once),
+            base_font_size: None,
+        },
     ));
+}
 
-    match curr_game_state.get() {
-        GameState::Restarting => next_game_state.set(GameState::Playing),
-        _ => {}
+// Distance from the player beyond which a kill counts as a long shot
+const LONG_SHOT_DISTANCE: f32 = 12.0;
+
+fn update_combo(
+    mut combo: ResMut<Combo>,
+    mut player: Query<(&mut Player, &Transform)>,
+    mut arrow_hit_events: EventReader<ArrowHit>,
+    mut arrow_missed_events: EventReader<ArrowMissed>,
+    mut spawn_floating_text_events: EventWriter<SpawnFloatingText>,
+    time: Res<Time>,
+) {
+    let (mut player, player_transform) = player.single_mut();
+
+    combo.window.tick(time.delta());
+    if combo.window.just_finished() {
+        combo.reset();
+    }
+
+    // Group kills by arrow so a single arrow taking out several enemies counts as a multi-kill
+    let mut kills_per_arrow: HashMap<Entity, u32> = HashMap::new();
+    for event in arrow_hit_events.read() {
+        let multiplier = combo.multiplier();
+        combo.register_hit();
+        if !event.killed {
+            continue;
+        }
+
+        let kills = kills_per_arrow.entry(event.arrow).or_default();
+        *kills += 1;
+
+        let mut points = 1;
+        if event.critical {
+            points += 1;
+        }
+        if *kills > 1 {
+            points += 1;
+        }
+        if player_transform.translation.distance(event.position) >= LONG_SHOT_DISTANCE {
+            points += 1;
+        }
+        player.score += multiplier * points;
+
+        // Kills show the points they earned instead of the damage
+        spawn_floating_text_events.send(SpawnFloatingText {
+            position: event.position,
+            text: format!("+{}", multiplier * points),
+            color: event.tier.color(),
+            font_size: FLOATING_TEXT_BIG_SIZE,
+        });
+    }
+
+    if arrow_missed_events.read().count() > 0 {
+        combo.reset();
     }
 }
 
-fn play_again(
+fn update_player_score(
+    player: Query<&Player>,
+    combo: Res<Combo>,
+    mut text: Query<(&mut Text, &mut Score, &mut Ui2DText)>,
+    time: Res<Time>,
+) {
+    let player = player.single();
+    let (mut text, mut score, mut ui_text) = text.single_mut();
+    let multiplier = combo.multiplier();
+
+    let value = if multiplier > 1 {
+        format!("Score: {}  x{}", player.score, multiplier)
+    } else {
+        format!("Score: {}", player.score)
+    };
+
+    // Bump the text whenever the score or multiplier changes
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+        score.pulse = Timer::from_seconds(0.25, TimerMode::Once);
+    }
+    score.pulse.tick(time.delta());
+
+    let pulse = 1.0 - score.pulse.fraction();
+    let base_font_size = *score.base_font_size.get_or_insert(ui_text.font_size);
+    ui_text.font_size = base_font_size * (1.0 + 0.3 * pulse);
+    text.sections[0].style.color = if multiplier > 1 {
+        Color::srgb(1.0, 0.85, 0.2)
+    } else {
+        Color::WHITE
+    };
+}
+
+fn enemies_walking(
+    mut enemies: Query<(&mut Transform, &Enemy), Without<Player>>,
+    player_query: Query<(&Transform, &Player), Without<Enemy>>,
+) {
+    // Get the player's position (assuming there is only one player)
+    let (player_transform, _) = player_query.single();
+
+    for (mut transform, _) in enemies.iter_mut() {
+        // Calculate the direction from the enemy to the player
+        let direction_to_player = player_transform.translation - transform.translation;
+
+        // Normalize the direction to get a unit vector
+        let direction = direction_to_player.normalize();
+
+        // Move the enemy towards the player
+        transform.translation += direction * 0.01; // Adjust the speed as necessary
+
+        // Calculate the new rotation for the enemy to face the player
+        // Assumes 2D movement on the XZ plane; you can adjust if using 3D
+        let target_rotation = Quat::from_rotation_arc(Vec3::Z, direction);
+
+        // Set the enemy's rotation
+        transform.rotation = target_rotation;
+    }
+}
+
+// Enemies closer than this to the player get a warning sign
+const WARNING_DISTANCE: f32 = 10.0;
+// How far past the danger radius an enemy has to go before its sign is released
+const WARNING_HYSTERESIS: f32 = 1.0;
+
+// Link enemies entering the danger zone to a new warning sign and release the ones that left it.
+// Each enemy knows its own sign, so this is a single pass over the enemies
+fn check_enemy_proximity(
     mut commands: Commands,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut next_game_state: ResMut<NextState<GameState>>,
-    transforms: Query<(Entity, &Transform)>,
-    ui_2d_icons: Query<(Entity, &Ui2DComponent), Without<Ui2DText>>,
-    ui_2d_text: Query<(Entity, &Ui2DComponent, &Ui2DText)>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Enemy, &Transform, Option<&EnemyWarning>)>,
+    mut warning_query: Query<&mut WarningSign>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
-        for (entity, _) in transforms.iter() {
-            commands.entity(entity).despawn();
+    let player_transform = player_query.single();
+    let release_distance = WARNING_DISTANCE + WARNING_HYSTERESIS;
+
+    for (enemy_entity, enemy, enemy_transform, enemy_warning) in enemy_query.iter() {
+        // Killed enemies are on their way out
+        if enemy.health <= 0.0 {
+            continue;
         }
 
-        for (entity, _) in ui_2d_icons.iter() {
-            if let Ok(_) = ui_2d_icons.get(entity) {
-                commands.entity(entity).despawn();
+        let distance_squared = player_transform
+            .translation
+            .distance_squared(enemy_transform.translation);
+
+        match enemy_warning {
+            // Check if the enemy is within the danger zone
+            None if distance_squared < WARNING_DISTANCE * WARNING_DISTANCE => {
+                // Spawn a warning sign and link it to the enemy
+                let sign = spawn_warning_exclamation(&mut commands, enemy_entity);
+                // The enemy may be despawned before the command is applied
+                commands
+                    .entity(enemy_entity)
+                    .try_insert(EnemyWarning { sign });
+            }
+            // Only let go once the enemy is clearly outside, so signs don't flicker on the edge
+            Some(enemy_warning) if distance_squared > release_distance * release_distance => {
+                if let Ok(mut warning_sign) = warning_query.get_mut(enemy_warning.sign) {
+                    warning_sign.enemy_entity = None;
+                }
+                commands.entity(enemy_entity).remove::<EnemyWarning>();
             }
+            _ => {}
         }
+    }
+}
 
-        for (entity, _, _) in ui_2d_text.iter() {
-            if let Ok(_) = ui_2d_text.get(entity) {
-                commands.entity(entity).despawn();
+// Warning sign size, in the reference resolution
+const WARNING_SIGN_SIZE: f32 = 60.0;
+
+fn spawn_warning_exclamation(commands: &mut Commands, enemy_entity: Entity) -> Entity {
+    // Spawn the warning sign linked to this enemy
+    commands
+        .spawn(WarningSignBundle::new(enemy_entity, WARNING_SIGN_SIZE))
+        .with_children(|parent| {
+            let sign = parent.parent_entity();
+            parent.spawn((
+                TextBundle::from_section(
+                    "!",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 50.0,
+                        color: Color::srgb(1.0, 0.0, 0.0),
+                    },
+                ),
+                WarningSignGlyph { sign },
+            ));
+
+            // Count badge in the top right corner, only shown for clusters
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 22.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        ..Default::default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                WarningSignCount { sign },
+            ));
+
+            // The arrow sits on the right edge of a full-size pivot that is rotated towards the enemy
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            justify_content: JustifyContent::FlexEnd,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    WarningSignArrow {},
+                ))
+                .with_children(|pivot| {
+                    pivot.spawn((
+                        TextBundle::from_section(
+                            ">",
+                            TextStyle {
+                                font: Default::default(),
+                                font_size: 40.0,
+                                color: Color::srgb(1.0, 0.0, 0.0),
+                            },
+                        ),
+                        WarningSignGlyph { sign },
+                    ));
+                });
+        })
+        .id()
+}
+
+// Pin the warning signs of off-screen enemies to the screen edge, pointing towards them,
+// and hide the ones whose enemy can already be seen
+#[allow(clippy::type_complexity)]
+fn update_warning_positions(
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    camera: Query<(&Camera, &GlobalTransform), With<ThirdPersonCamera>>,
+    mut warning_query: Query<(&mut Style, &Children, &mut WarningSign)>,
+    mut arrows: Query<&mut Transform, (With<WarningSignArrow>, Without<Player>, Without<Enemy>)>,
+    settings: Res<Settings>,
+) {
+    let player_transform = player_query.single();
+    let (camera, camera_transform) = camera.single();
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let center = viewport_size / 2.0;
+    let ui_scale = viewport_size.y / MAX_HEIGHT * settings.accessibility.ui_scale;
+    let world_to_camera = camera_transform.affine().inverse();
+
+    for (mut style, children, mut warning_sign) in warning_query.iter_mut() {
+        // Signs of dead or released enemies stay where they were while they fade out
+        let Some(enemy_transform) = warning_sign
+            .enemy_entity
+            .and_then(|enemy| enemy_query.get(enemy).ok())
+        else {
+            continue;
+        };
+
+        // No need for a warning when the enemy is on screen
+        let on_screen = camera
+            .world_to_viewport(camera_transform, enemy_transform.translation)
+            .is_some_and(|position| {
+                position.cmpge(Vec2::ZERO).all() && position.cmple(viewport_size).all()
+            });
+        if on_screen {
+            warning_sign.screen_position = None;
+            continue;
+        }
+
+        // Direction to the enemy as seen from above the camera, forward being up on screen
+        let local_position = world_to_camera.transform_point3(enemy_transform.translation);
+        let direction = Vec2::new(local_position.x, local_position.z)
+            .try_normalize()
+            .unwrap_or(Vec2::Y);
+
+        // Closer enemies get bigger signs
+        let distance = player_transform
+            .translation
+            .distance(enemy_transform.translation);
+        let scale = ui_scale * (1.0 - 0.5 * (distance / WARNING_DISTANCE).clamp(0.0, 1.0));
+
+        // Walk from the center of the screen towards the enemy until hitting the inset edge
+        let inset = center - Vec2::splat(WARNING_SIGN_SIZE * scale / 2.0);
+        let mut reach = f32::MAX;
+        if direction.x != 0.0 {
+            reach = reach.min(inset.x / direction.x.abs());
+        }
+        if direction.y != 0.0 {
+            reach = reach.min(inset.y / direction.y.abs());
+        }
+        let screen_position = center + direction * reach;
+
+        // Update the position of the warning sign
+        style.left = Val::Px(screen_position.x - WARNING_SIGN_SIZE / 2.0);
+        style.top = Val::Px(screen_position.y - WARNING_SIGN_SIZE / 2.0);
+        warning_sign.screen_position = Some(screen_position);
+        warning_sign.scale = scale;
+
+        for &child in children.iter() {
+            if let Ok(mut arrow_transform) = arrows.get_mut(child) {
+                arrow_transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
             }
         }
+    }
+}
 
-        next_game_state.set(GameState::Restarting);
+// World units from the player to the edge of the minimap at 1x zoom
+const MINIMAP_RANGE: f32 = 15.0;
+const MINIMAP_ENEMY_SIZE: f32 = 8.0;
+const MINIMAP_ARROW_SIZE: f32 = 4.0;
+
+fn setup_minimap(mut commands: Commands, settings: Res<Settings>) {
+    let diameter = settings.minimap.radius * 2.0;
+
+    // The arena is the area covered by the ground tiles
+    let (min, max) = GROUND_TILES.iter().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), (position, _)| {
+            (
+                min.min(position.xz() - GROUND_TILE_HALF_SIZE),
+                max.max(position.xz() + GROUND_TILE_HALF_SIZE),
+            )
+        },
+    );
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    overflow: Overflow::clip(),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+                border_radius: BorderRadius::all(Val::Percent(50.0)),
+                ..Default::default()
+            },
+            Ui2DComponent::new(Vec2::new(20.0, 20.0), Vec2::splat(diameter)),
+            Minimap {},
+        ))
+        .with_children(|parent| {
+            for (position, color) in GROUND_TILES {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            ..Default::default()
+                        },
+                        background_color: BackgroundColor(color.with_alpha(0.35)),
+                        ..Default::default()
+                    },
+                    MinimapShape {
+                        center: position.xz(),
+                        size: Vec2::splat(GROUND_TILE_HALF_SIZE * 2.0),
+                    },
+                ));
+            }
+
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..Default::default()
+                    },
+                    border_color: BorderColor(Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                    ..Default::default()
+                },
+                MinimapShape {
+                    center: (min + max) / 2.0,
+                    size: max - min,
+                },
+            ));
+
+            // The player always sits in the middle, facing up
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "^",
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                MinimapPlayer {},
+            ));
+        });
+}
+
+// Draw everything around the player on the minimap, rotated so the player's forward is up
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_minimap(
+    mut commands: Commands,
+    mut minimap: Query<(Entity, &mut Ui2DComponent), With<Minimap>>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &Transform, &Enemy)>,
+    arrows: Query<(Entity, &Transform), With<Arrow>>,
+    mut blips: Query<(Entity, &MinimapBlip, &mut Style), Without<MinimapShape>>,
+    mut shapes: Query<
+        (&MinimapShape, &mut Style, &mut Transform),
+        (Without<Player>, Without<Enemy>, Without<Arrow>),
+    >,
+    mut player_marker: Query<
+        &mut Style,
+        (
+            With<MinimapPlayer>,
+            Without<MinimapShape>,
+            Without<MinimapBlip>,
+        ),
+    >,
+    window: Query<&Window>,
+    settings: Res<Settings>,
+) {
+    let Ok((minimap_entity, mut minimap_component)) = minimap.get_single_mut() else {
+        return;
+    };
+    let player_transform = player.single();
+
+    // Match the size update_ui_2d gives the minimap itself
+    minimap_component.base_size = Vec2::splat(settings.minimap.radius * 2.0);
+    let window_height = window
+        .iter()
+        .next()
+        .map_or(MIN_HEIGHT, |window| window.height());
+    let scale = window_height / MAX_HEIGHT * settings.accessibility.ui_scale;
+    let radius = settings.minimap.radius * scale;
+    let pixels_per_unit = radius / (MINIMAP_RANGE / settings.minimap.zoom);
+
+    // World offsets from the player to minimap offsets from the center, with forward pointing up
+    let world_to_player = player_transform.rotation.inverse();
+    let to_minimap = |world_position: Vec2| {
+        let offset =
+            Vec3::new(world_position.x, 0.0, world_position.y) - player_transform.translation;
+        let local = world_to_player * offset;
+        Vec2::new(local.x, local.z) * pixels_per_unit
+    };
+    let world_x_axis = world_to_player * Vec3::X;
+    let rotation = Quat::from_rotation_z(world_x_axis.z.atan2(world_x_axis.x));
+
+    for (shape, mut style, mut transform) in shapes.iter_mut() {
+        let position = to_minimap(shape.center) + Vec2::splat(radius);
+        let size = shape.size * pixels_per_unit;
+        style.left = Val::Px(position.x - size.x / 2.0);
+        style.top = Val::Px(position.y - size.y / 2.0);
+        style.width = Val::Px(size.x);
+        style.height = Val::Px(size.y);
+        transform.rotation = rotation;
+    }
+
+    // The player marker is never rotated, only kept in the middle
+    for mut style in player_marker.iter_mut() {
+        style.left = Val::Px(radius - 6.0);
+        style.top = Val::Px(radius - 8.0);
+    }
+
+    // Blips are clamped to the rim so threats out of range still show their direction
+    let place_blip = |style: &mut Style, world_position: Vec3, size: f32| {
+        let offset = to_minimap(world_position.xz()).clamp_length_max(radius - size / 2.0);
+        let position = offset + Vec2::splat(radius - size / 2.0);
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
+        style.width = Val::Px(size);
+        style.height = Val::Px(size);
+    };
+
+    let mut tracked = HashSet::new();
+    for (blip_entity, blip, mut style) in blips.iter_mut() {
+        if let Ok((_, transform, _)) = enemies.get(blip.target) {
+            place_blip(
+                &mut style,
+                transform.translation,
+                MINIMAP_ENEMY_SIZE * scale,
+            );
+        } else if let Ok((_, transform)) = arrows.get(blip.target) {
+            place_blip(
+                &mut style,
+                transform.translation,
+                MINIMAP_ARROW_SIZE * scale,
+            );
+        } else {
+            commands.entity(blip_entity).despawn_recursive();
+            continue;
+        }
+        tracked.insert(blip.target);
+    }
+
+    // New enemies and arrows get a blip, placed on the next frame
+    let new_blips = enemies
+        .iter()
+        .filter(|(entity, ..)| !tracked.contains(entity))
+        .map(|(entity, _, enemy)| (entity, enemy.minimap_color()))
+        .chain(
+            arrows
+                .iter()
+                .filter(|(entity, _)| !tracked.contains(entity))
+                .map(|(entity, _)| (entity, Color::WHITE)),
+        );
+    for (target, color) in new_blips {
+        let blip = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(color),
+                    visibility: Visibility::Inherited,
+                    border_radius: BorderRadius::all(Val::Percent(50.0)),
+                    ..Default::default()
+                },
+                MinimapBlip { target },
+            ))
+            .id();
+        commands.entity(minimap_entity).add_child(blip);
+    }
+}
+
+// Threat animation tuning
+const WARNING_FADE_SPEED: f32 = 4.0; // Full fade in or out in a quarter of a second
+const WARNING_MIN_PULSE_RATE: f32 = 1.5; // Pulses per second at the lowest threat
+const WARNING_MAX_PULSE_RATE: f32 = 6.0; // Pulses per second at the highest threat
+const WARNING_APPROACH_SPEED: f32 = 1.5; // Approach speed counted as the highest threat
+const WARNING_CLUSTER_RADIUS: f32 = 70.0; // Signs closer than this on screen are merged
+
+// Pulse and color the warning signs by how threatening their enemy is, fade them in and out,
+// and merge signs that overlap on screen into a single one with a count
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn animate_warning_signs(
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    mut warning_query: Query<
+        (
+            Entity,
+            &WarningSign,
+            &mut WarningSignAnimation,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        (Without<Player>, Without<Enemy>),
+    >,
+    mut glyphs: Query<(&mut Text, &WarningSignGlyph), Without<WarningSignCount>>,
+    mut counts: Query<(&mut Text, &mut Visibility, &WarningSignCount), Without<WarningSign>>,
+    window: Query<&Window>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let player_transform = player_query.single();
+    let delta = time.delta_seconds();
+
+    // Update the threat of every sign whose enemy is still around
+    for (_, warning_sign, mut animation, _, _) in warning_query.iter_mut() {
+        let Some(enemy_transform) = warning_sign
+            .enemy_entity
+            .and_then(|enemy| enemy_query.get(enemy).ok())
+        else {
+            continue;
+        };
+        let distance = player_transform
+            .translation
+            .distance(enemy_transform.translation);
+        if let Some(last_distance) = animation.last_distance {
+            if delta > 0.0 {
+                let approach_speed = (last_distance - distance) / delta;
+                animation.approach_speed += (approach_speed - animation.approach_speed) * 0.1;
+            }
+        }
+        animation.last_distance = Some(distance);
+
+        let proximity = 1.0 - (distance / WARNING_DISTANCE).clamp(0.0, 1.0);
+        let approach = (animation.approach_speed / WARNING_APPROACH_SPEED).clamp(0.0, 1.0);
+        animation.threat = (0.7 * proximity + 0.3 * approach).clamp(0.0, 1.0);
+    }
+
+    // Greedily cluster the visible signs, the most threatening one leads each cluster
+    let window_height = window
+        .iter()
+        .next()
+        .map_or(MIN_HEIGHT, |window| window.height());
+    let cluster_radius =
+        WARNING_CLUSTER_RADIUS * window_height / MAX_HEIGHT * settings.accessibility.ui_scale;
+    let mut candidates: Vec<(Entity, Vec2, f32)> = warning_query
+        .iter()
+        .filter(|(_, warning_sign, ..)| {
+            warning_sign
+                .enemy_entity
+                .is_some_and(|enemy| enemy_query.contains(enemy))
+        })
+        .filter_map(|(entity, warning_sign, animation, ..)| {
+            warning_sign
+                .screen_position
+                .map(|position| (entity, position, animation.threat))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut leaders: HashMap<Entity, (Vec2, u32)> = HashMap::new();
+    for (entity, position, _) in candidates {
+        match leaders
+            .values_mut()
+            .find(|(leader_position, _)| leader_position.distance(position) < cluster_radius)
+        {
+            Some((_, count)) => *count += 1,
+            None => {
+                leaders.insert(entity, (position, 1));
+            }
+        }
+    }
+
+    // Fade, pulse and color every sign
+    let mut appearances: HashMap<Entity, Color> = HashMap::new();
+    for (entity, warning_sign, mut animation, mut transform, mut visibility) in
+        warning_query.iter_mut()
+    {
+        let target_fade = if leaders.contains_key(&entity) {
+            1.0
+        } else {
+            0.0
+        };
+        animation.fade = if target_fade > animation.fade {
+            (animation.fade + WARNING_FADE_SPEED * delta).min(target_fade)
+        } else {
+            (animation.fade - WARNING_FADE_SPEED * delta).max(target_fade)
+        };
+
+        let pulse_rate = WARNING_MIN_PULSE_RATE.lerp(WARNING_MAX_PULSE_RATE, animation.threat);
+        animation.phase =
+            (animation.phase + pulse_rate * std::f32::consts::TAU * delta) % std::f32::consts::TAU;
+        let pulse = if settings.accessibility.reduced_motion {
+            0.0
+        } else {
+            animation.phase.sin() * 0.5 + 0.5
+        };
+
+        let scale = warning_sign.scale * (1.0 + 0.15 * pulse * animation.threat);
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        *visibility = if animation.fade > 0.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        // Yellow at the edge of the danger zone, red up close
+        let color = Color::srgb(1.0, 0.85, 0.0)
+            .mix(&Color::srgb(1.0, 0.0, 0.0), animation.threat)
+            .with_alpha(animation.fade * (0.75 + 0.25 * pulse));
+        appearances.insert(entity, color);
+    }
+
+    for (mut text, glyph) in glyphs.iter_mut() {
+        if let Some(color) = appearances.get(&glyph.sign) {
+            text.sections[0].style.color = *color;
+        }
+    }
+
+    for (mut text, mut visibility, count) in counts.iter_mut() {
+        match leaders.get(&count.sign) {
+            Some((_, count)) if *count > 1 => {
+                text.sections[0].value = count.to_string();
+                *visibility = Visibility::Inherited;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+// Despawn the signs of dead or released enemies once they have faded out
+fn remove_far_warning_signs(
+    enemy_query: Query<(), With<Enemy>>,
+    warning_query: Query<(Entity, &WarningSign, &WarningSignAnimation)>,
+    mut commands: Commands,
+) {
+    for (warning_entity, warning_sign, animation) in warning_query.iter() {
+        let linked = warning_sign
+            .enemy_entity
+            .is_some_and(|enemy| enemy_query.contains(enemy));
+        if !linked && animation.fade <= 0.0 {
+            commands.entity(warning_entity).despawn_recursive();
+        }
+    }
+}
+
+fn game_over(
+    mut commands: Commands,
+    curr_game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    commands.spawn((
+        TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: "Game Over!\nPress R to restart".to_string(),
+                    style: TextStyle {
+                        font: Default::default(),
+                        font_size: 75.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        Ui2DComponent::new(
+            Vec2::new(
+                MAX_WIDTH / 4.0 + MAX_WIDTH / 32.0,
+                MAX_HEIGHT / 4.0 + MAX_HEIGHT / 16.0,
+            ),
+            Vec2::new(MAX_WIDTH / 2.0, MAX_HEIGHT / 2.0),
+        ),
+        Ui2DText { font_size: 75.0 },
+        GameOverText {},
+    ));
+
+    commands.spawn((
+        TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: Default::default(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        Ui2DComponent::new(
+            Vec2::new(MAX_WIDTH / 4.0 + MAX_WIDTH / 32.0, MAX_HEIGHT / 16.0),
+            Vec2::new(MAX_WIDTH / 2.0, MAX_HEIGHT / 2.5),
+        ),
+        Ui2DText { font_size: 22.0 },
+        LeaderboardText {},
+    ));
+
+    commands.spawn((
+        TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: Default::default(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        Ui2DComponent::new(
+            Vec2::new(MAX_WIDTH / 32.0, MAX_HEIGHT / 16.0),
+            Vec2::new(MAX_WIDTH / 5.0, MAX_HEIGHT / 2.5),
+        ),
+        Ui2DText { font_size: 22.0 },
+        RunSummaryText {},
+    ));
+
+    match curr_game_state.get() {
+        GameState::Restarting => next_game_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+fn play_again(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    transforms: Query<(Entity, &Transform)>,
+    ui_2d_icons: Query<(Entity, &Ui2DComponent), Without<Ui2DText>>,
+    ui_2d_text: Query<(Entity, &Ui2DComponent, &Ui2DText)>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        for (entity, _) in transforms.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        for (entity, _) in ui_2d_icons.iter() {
+            if let Ok(_) = ui_2d_icons.get(entity) {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        for (entity, _, _) in ui_2d_text.iter() {
+            if let Ok(_) = ui_2d_text.get(entity) {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        next_game_state.set(GameState::Restarting);
+    }
+}
+
+fn open_settings_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_game_state.set(GameState::Settings);
+    }
+}
+
+fn spawn_settings_menu(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: Default::default(),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+            ..Default::default()
+        },
+        Ui2DComponent::new(
+            Vec2::new(MAX_WIDTH / 4.0, MAX_HEIGHT / 8.0),
+            Vec2::new(MAX_WIDTH / 2.0, MAX_HEIGHT * 3.0 / 4.0),
+        ),
+        Ui2DText { font_size: 30.0 },
+        SettingsMenuText {},
+    ));
+}
+
+fn despawn_settings_menu(mut commands: Commands, menu: Query<Entity, With<SettingsMenuText>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn navigate_settings_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut selection: ResMut<SettingsMenuSelection>,
+    mut settings: ResMut<Settings>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let option_count = SettingsOption::ALL.len();
+
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        selection.0 = (selection.0 + option_count - 1) % option_count;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        selection.0 = (selection.0 + 1) % option_count;
+    }
+
+    let option = SettingsOption::ALL[selection.0];
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        option.adjust(&mut settings, -1.0);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        option.adjust(&mut settings, 1.0);
+    }
+
+    // Leaving the menu persists whatever was changed
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        settings.save();
+        next_game_state.set(GameState::Playing);
+    }
+}
+
+fn update_settings_menu_text(
+    selection: Res<SettingsMenuSelection>,
+    settings: Res<Settings>,
+    mut menu_text: Query<&mut Text, With<SettingsMenuText>>,
+) {
+    let mut lines = vec!["Settings\n".to_string()];
+    for (index, option) in SettingsOption::ALL.iter().enumerate() {
+        let marker = if index == selection.0 { ">" } else { " " };
+        lines.push(format!(
+            "{} {}: < {} >",
+            marker,
+            option.label(),
+            option.value(&settings)
+        ));
+    }
+    lines.push("\nUp/Down to select, Left/Right to change, Esc to save and resume".to_string());
+
+    for mut text in menu_text.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut player: Query<&mut Player>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // The charge timer has to run for the whole charge, or the strength caps early
+    for mut player in player.iter_mut() {
+        let max_charge_duration = settings.gameplay.max_charge_duration;
+        player.max_charge_duration = max_charge_duration;
+        player
+            .charge_timer
+            .set_duration(Duration::from_secs_f32(max_charge_duration));
+    }
+
+    for mut window in windows.iter_mut() {
+        let (width, height) = settings.display.resolution;
+        if window.resolution.width() != width || window.resolution.height() != height {
+            window.resolution.set(width, height);
+        }
+        window.mode = window_mode(&settings);
+        window.present_mode = present_mode(&settings);
+    }
+
+    global_volume.volume = Volume::new(settings.audio.master_volume);
+}
+
+fn start_run(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut particle_pool: ResMut<ParticlePool>,
+    mut floating_text_pool: ResMut<FloatingTextPool>,
+) {
+    // Every run gets its own seed so it can be recorded alongside the score
+    let seed = rand::thread_rng().gen::<u64>();
+
+    commands.insert_resource(RunInfo {
+        seed,
+        mode: if settings.gameplay.hardcore {
+            GameMode::Hardcore
+        } else {
+            GameMode::Normal
+        },
+        duration: Stopwatch::new(),
+    });
+    commands.insert_resource(RunRng(StdRng::seed_from_u64(seed)));
+    commands.insert_resource(RunStats::default());
+    commands.insert_resource(Combo::default());
+
+    // Pooled particles and texts are despawned with everything else when a run restarts
+    particle_pool.clear();
+    floating_text_pool.clear();
+}
+
+fn tick_run_duration(mut run_info: ResMut<RunInfo>, time: Res<Time>) {
+    run_info.duration.tick(time.delta());
+}
+
+fn track_run_stats(
+    mut run_stats: ResMut<RunStats>,
+    mut shot_fired_events: EventReader<ShotFired>,
+    mut arrow_hit_events: EventReader<ArrowHit>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, With<Enemy>>,
+    time: Res<Time>,
+) {
+    for event in shot_fired_events.read() {
+        match (event.ability, event.tier) {
+            (Some(AbilityId::PowerShot), _) => run_stats.power_shots += 1,
+            (Some(_), _) => run_stats.ability_shots += 1,
+            (None, ChargeTier::Quick) => run_stats.quick_shots += 1,
+            (None, ChargeTier::Medium) => run_stats.medium_shots += 1,
+            (None, ChargeTier::Power) => run_stats.power_shots += 1,
+        }
+    }
+
+    // One arrow can hit several enemies in the same frame, but only lands once
+    let mut landed_arrows = Vec::new();
+    for event in arrow_hit_events.read() {
+        run_stats.hits += 1;
+        if event.killed {
+            run_stats.kills += 1;
+        }
+        if !landed_arrows.contains(&event.arrow) {
+            landed_arrows.push(event.arrow);
+        }
+    }
+    run_stats.arrows_landed += landed_arrows.len() as u32;
+
+    let player_translation = player.single().translation;
+    if let Some(last_position) = run_stats.last_player_position {
+        run_stats.distance_walked += last_position.xz().distance(player_translation.xz());
+    }
+    run_stats.last_player_position = Some(player_translation);
+    run_stats.time_survived += time.delta_seconds();
+
+    for enemy_transform in enemies.iter() {
+        let distance = player_translation.distance(enemy_transform.translation);
+        let closest = run_stats
+            .closest_call
+            .map_or(distance, |closest| closest.min(distance));
+        run_stats.closest_call = Some(closest);
+    }
+}
+
+// Keys pressed during the run, like Space, would otherwise be typed into the name
+fn clear_keyboard_events(mut keyboard_events: ResMut<Events<KeyboardInput>>) {
+    keyboard_events.clear();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_game_over(
+    mut commands: Commands,
+    player: Query<&Player>,
+    leaderboard: Res<Leaderboard>,
+    mut highlight: ResMut<LeaderboardHighlight>,
+    mut leaderboard_text: Query<&mut Visibility, (With<LeaderboardText>, Without<RunSummaryText>)>,
+    mut run_summary_text: Query<(&mut Text, &mut Visibility), With<RunSummaryText>>,
+    run_info: Res<RunInfo>,
+    run_stats: Res<RunStats>,
+    settings: Res<Settings>,
+) {
+    let score = player.single().score;
+    highlight.0 = None;
+
+    if leaderboard.qualifies(score) {
+        commands.init_resource::<NameEntry>();
+    }
+
+    for mut visibility in leaderboard_text.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+
+    let record = RunRecord {
+        timestamp: unix_timestamp(),
+        seed: run_info.seed,
+        mode: run_info.mode,
+        score,
+        accuracy: run_stats.accuracy(),
+        stats: run_stats.clone(),
+    };
+    record.export(settings.gameplay.stats_export);
+
+    for (mut text, mut visibility) in run_summary_text.iter_mut() {
+        text.sections[0].value = record.summary();
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn name_entry_input(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut name_entry: ResMut<NameEntry>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut highlight: ResMut<LeaderboardHighlight>,
+    player: Query<&Player>,
+    run_info: Res<RunInfo>,
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(characters) => {
+                for character in characters.chars() {
+                    if (character.is_alphanumeric() || character == ' ')
+                        && name_entry.name.chars().count() < MAX_NAME_LENGTH
+                    {
+                        name_entry.name.push(character);
+                    }
+                }
+            }
+            Key::Space if name_entry.name.chars().count() < MAX_NAME_LENGTH => {
+                name_entry.name.push(' ');
+            }
+            Key::Backspace => {
+                name_entry.name.pop();
+            }
+            Key::Enter => {
+                let name = name_entry.name.trim();
+                highlight.0 = leaderboard.insert(LeaderboardEntry {
+                    name: if name.is_empty() { "Anonymous" } else { name }.to_string(),
+                    score: player.single().score,
+                    timestamp: unix_timestamp(),
+                    seed: run_info.seed,
+                    mode: run_info.mode,
+                    duration_secs: run_info.duration.elapsed_secs(),
+                });
+                leaderboard.save();
+                commands.remove_resource::<NameEntry>();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_leaderboard_text(
+    leaderboard: Res<Leaderboard>,
+    highlight: Res<LeaderboardHighlight>,
+    name_entry: Option<Res<NameEntry>>,
+    curr_game_state: Res<State<GameState>>,
+    mut leaderboard_text: Query<&mut Text, With<LeaderboardText>>,
+) {
+    let mut value = match name_entry {
+        Some(name_entry) => format!(
+            "New high score! Enter your name: {}_\nPress Enter to confirm\n\n",
+            name_entry.name
+        ),
+        None => "".to_string(),
+    };
+    value.push_str(&leaderboard.format_table(highlight.0));
+    if *curr_game_state.get() == GameState::Leaderboard {
+        value.push_str("\n\nPress L or Esc to resume");
+    }
+
+    for mut text in leaderboard_text.iter_mut() {
+        text.sections[0].value.clone_from(&value);
+    }
+}
+
+fn toggle_leaderboard_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    curr_game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    match curr_game_state.get() {
+        GameState::Playing if keyboard_input.just_pressed(KeyCode::KeyL) => {
+            next_game_state.set(GameState::Leaderboard);
+        }
+        GameState::Leaderboard
+            if keyboard_input.any_just_pressed([KeyCode::KeyL, KeyCode::Escape]) =>
+        {
+            next_game_state.set(GameState::Playing);
+        }
+        _ => {}
+    }
+}
+
+fn show_leaderboard(
+    mut highlight: ResMut<LeaderboardHighlight>,
+    mut leaderboard_text: Query<&mut Visibility, With<LeaderboardText>>,
+) {
+    highlight.0 = None;
+    for mut visibility in leaderboard_text.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn hide_leaderboard(mut leaderboard_text: Query<&mut Visibility, With<LeaderboardText>>) {
+    for mut visibility in leaderboard_text.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+// Length of each dash of the trajectory preview and how finely the flight is traced
+const TRAJECTORY_DASH_LENGTH: f32 = 0.4;
+const TRAJECTORY_STEP: f32 = 0.1;
+const TRAJECTORY_MARKER_SIZE: f32 = 0.25;
+
+// Trace the arrow releasing now would fire with the same motion as arrow_movement, and
+// mark where it would hit the ground or an enemy
+fn draw_trajectory_preview(
+    mut gizmos: Gizmos,
+    player: Query<(&Transform, &Player), Without<Camera>>,
+    camera: Query<&Transform, (With<ThirdPersonCamera>, Without<Player>)>,
+    enemies: Query<&Transform, With<Enemy>>,
+    settings: Res<Settings>,
+) {
+    let (player_transform, player) = player.single();
+    let strength = player.charge_ratio();
+    if !settings.input.trajectory_preview || !player.is_charging || strength <= 0.0 {
+        return;
+    }
+
+    let (mut position, direction) =
+        shot_origin(player_transform, player, camera.single(), &settings);
+    let mut arrow = Arrow::charged(direction, strength);
+    let color = ChargeTier::from_strength(strength).color();
+    let delta = TRAJECTORY_STEP / arrow.speed;
+
+    loop {
+        let previous = position;
+        position = arrow.step(position, delta);
+
+        if let Some((hit, _)) = enemies
+            .iter()
+            .find_map(|enemy_transform| hit_location(enemy_transform, position, direction))
+        {
+            gizmos.line(previous, hit, color);
+            gizmos.sphere(hit, Quat::IDENTITY, TRAJECTORY_MARKER_SIZE, color);
+            return;
+        }
+
+        if arrow.is_finished(position) {
+            if position.y <= Arrow::GROUND_HEIGHT {
+                let t = (previous.y - Arrow::GROUND_HEIGHT) / (previous.y - position.y);
+                let impact = previous.lerp(position, t);
+                gizmos.circle(impact, Dir3::Y, TRAJECTORY_MARKER_SIZE, color);
+                gizmos.circle(impact, Dir3::Y, TRAJECTORY_MARKER_SIZE * 0.4, color);
+            }
+            return;
+        }
+
+        // Every other dash is left out to make the line dotted
+        if (arrow.distance_travelled / (TRAJECTORY_DASH_LENGTH * 2.0)).fract() < 0.5 {
+            gizmos.line(previous, position, color);
+        }
+    }
+}
+
+// Crosshair size, in the reference resolution
+const CROSSHAIR_SIZE: f32 = 100.0;
+const CROSSHAIR_COLOR: Vec4 = Vec4::new(0.5, 0.5, 0.5, 0.5);
+// How far along the shot direction the aim point is projected, matches the arrow range
+const AIM_DISTANCE: f32 = 20.0;
+const CROSSHAIR_MOVE_SPREAD: f32 = 0.15;
+const CROSSHAIR_CHARGE_TIGHTEN: f32 = 0.08;
+
+// Keep the crosshair on the point the arrows fly towards and animate its spread
+fn update_crosshair(
+    mut crosshair: Query<(
+        &mut Crosshair,
+        &mut Style,
+        &mut Visibility,
+        &Handle<CrosshairUiMaterial>,
+    )>,
+    mut crosshair_ui_materials: ResMut<Assets<CrosshairUiMaterial>>,
+    player: Query<(&Transform, &Player), Without<Camera>>,
+    camera: Query<(&Camera, &GlobalTransform, &Transform), With<ThirdPersonCamera>>,
+    window: Query<&Window>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let (player_transform, player) = player.single();
+    let (camera, camera_global_transform, camera_transform) = camera.single();
+    let (spawn_position, direction) =
+        shot_origin(player_transform, player, camera_transform, &settings);
+    let aim_point = spawn_position + direction * AIM_DISTANCE;
+    let window_height = window
+        .iter()
+        .next()
+        .map_or(MIN_HEIGHT, |window| window.height());
+    let size = CROSSHAIR_SIZE * window_height / MAX_HEIGHT * settings.accessibility.ui_scale;
+
+    for (mut crosshair, mut style, mut visibility, material_handle) in crosshair.iter_mut() {
+        match camera.world_to_viewport(camera_global_transform, aim_point) {
+            Some(screen_position) => {
+                style.left = Val::Px(screen_position.x - size / 2.0);
+                style.top = Val::Px(screen_position.y - size / 2.0);
+                style.width = Val::Px(size);
+                style.height = Val::Px(size);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+
+        // Spread out while moving, tighten as the bow is drawn
+        let moving = crosshair
+            .last_player_position
+            .is_some_and(|position| position.distance(player_transform.translation) > 0.001);
+        crosshair.last_player_position = Some(player_transform.translation);
+
+        let target_spread = if !settings.crosshair.dynamic {
+            0.0
+        } else if player.is_charging {
+            -CROSSHAIR_CHARGE_TIGHTEN * player.charge_ratio()
+        } else if moving {
+            CROSSHAIR_MOVE_SPREAD
+        } else {
+            0.0
+        };
+        let easing = 1.0 - (-12.0 * time.delta_seconds()).exp();
+        crosshair.spread += (target_spread - crosshair.spread) * easing;
+
+        if let Some(material) = crosshair_ui_materials.get_mut(material_handle) {
+            material.apply_settings(&settings);
+            material.spread = crosshair.spread;
+        }
+    }
+}
+
+// Show the hit-marker on every hit and flash the crosshair gold on critical ones
+fn flash_crosshair_on_hit(
+    mut arrow_hit_events: EventReader<ArrowHit>,
+    mut crosshair: Query<(&mut Crosshair, &Handle<CrosshairUiMaterial>)>,
+    mut crosshair_ui_materials: ResMut<Assets<CrosshairUiMaterial>>,
+    time: Res<Time>,
+) {
+    let mut hit = false;
+    let mut critical_hit = false;
+    for event in arrow_hit_events.read() {
+        hit = true;
+        critical_hit |= event.critical;
+    }
+
+    for (mut crosshair, material_handle) in crosshair.iter_mut() {
+        if hit {
+            crosshair.hit_marker = Timer::from_seconds(0.25, TimerMode::Once);
+        }
+        if critical_hit {
+            crosshair.flash = Timer::from_seconds(0.3, TimerMode::Once);
+        }
+        crosshair.hit_marker.tick(time.delta());
+        crosshair.flash.tick(time.delta());
+
+        // Fade from gold back to the resting grey
+        let Some(material) = crosshair_ui_materials.get_mut(material_handle) else {
+            continue;
+        };
+        let flash = 1.0 - crosshair.flash.fraction();
+        material.color = CROSSHAIR_COLOR.lerp(Vec4::new(1.0, 0.8, 0.1, 1.0), flash);
+        material.hit_marker = 1.0 - crosshair.hit_marker.fraction();
+    }
+}
+
+// Floating text sizes in the reference resolution, crits and kills use the big one
+const FLOATING_TEXT_SIZE: f32 = 24.0;
+const FLOATING_TEXT_BIG_SIZE: f32 = 36.0;
+const FLOATING_TEXT_SECS: f32 = 0.8;
+const FLOATING_TEXT_RISE: f32 = 0.5;
+
+// Hits that don't kill show their damage, kills show points from update_combo instead
+fn spawn_damage_numbers(
+    mut arrow_hit_events: EventReader<ArrowHit>,
+    mut spawn_floating_text_events: EventWriter<SpawnFloatingText>,
+) {
+    for event in arrow_hit_events.read().filter(|event| !event.killed) {
+        let (text, font_size) = if event.critical {
+            (format!("CRIT! {:.1}", event.damage), FLOATING_TEXT_BIG_SIZE)
+        } else {
+            (format!("{:.1}", event.damage), FLOATING_TEXT_SIZE)
+        };
+        spawn_floating_text_events.send(SpawnFloatingText {
+            position: event.position,
+            text,
+            color: event.tier.color(),
+            font_size,
+        });
+    }
+}
+
+// Reuse a finished text if there is one, spawn a new one while the pool has room, and
+// otherwise take over the oldest text still on screen
+fn spawn_floating_text(
+    mut commands: Commands,
+    mut spawn_floating_text_events: EventReader<SpawnFloatingText>,
+    mut floating_text_pool: ResMut<FloatingTextPool>,
+    mut floating_text: Query<(Entity, &mut FloatingText, &mut Text)>,
+) {
+    for event in spawn_floating_text_events.read() {
+        let reused = floating_text_pool.free.pop().or_else(|| {
+            (floating_text_pool.count >= FloatingTextPool::MAX_TEXTS)
+                .then(|| {
+                    // Finished texts are already in the free list, only take over running ones
+                    floating_text
+                        .iter()
+                        .filter(|(_, floating, _)| !floating.lifetime.finished())
+                        .max_by(|(_, a, _), (_, b, _)| {
+                            a.lifetime.elapsed().cmp(&b.lifetime.elapsed())
+                        })
+                        .map(|(entity, _, _)| entity)
+                })
+                .flatten()
+        });
+
+        let section = TextSection {
+            value: event.text.clone(),
+            style: TextStyle {
+                font: Default::default(),
+                font_size: event.font_size,
+                color: event.color,
+            },
+        };
+        let floating = FloatingText {
+            world_position: event.position,
+            lifetime: Timer::from_seconds(FLOATING_TEXT_SECS, TimerMode::Once),
+            font_size: event.font_size,
+        };
+
+        if let Some((_, mut current, mut text)) =
+            reused.and_then(|entity| floating_text.get_mut(entity).ok())
+        {
+            *current = floating;
+            text.sections = vec![section];
+        } else {
+            floating_text_pool.count += 1;
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(section.value, section.style),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                floating,
+            ));
+        }
+    }
+}
+
+fn update_floating_text(
+    mut floating_text: Query<(
+        Entity,
+        &mut FloatingText,
+        &mut Style,
+        &mut Text,
+        &mut Visibility,
+        &Node,
+    )>,
+    mut floating_text_pool: ResMut<FloatingTextPool>,
+    camera: Query<(&Camera, &GlobalTransform), With<ThirdPersonCamera>>,
+    window: Query<&Window>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let (camera, camera_transform) = camera.single();
+    let window_height = window
+        .iter()
+        .next()
+        .map_or(MIN_HEIGHT, |window| window.height());
+    let ui_scale = (window_height / MAX_HEIGHT) * settings.accessibility.ui_scale;
+
+    for (entity, mut floating, mut style, mut text, mut visibility, node) in
+        floating_text.iter_mut()
+    {
+        if floating.lifetime.finished() {
+            continue;
+        }
+
+        floating.lifetime.tick(time.delta());
+        if floating.lifetime.finished() {
+            *visibility = Visibility::Hidden;
+            floating_text_pool.free.push(entity);
+            continue;
+        }
+
+        // Rise over the lifetime of the text, starting a bit above the impact
+        let progress = floating.lifetime.fraction();
+        let world_position =
+            floating.world_position + Vec3::Y * FLOATING_TEXT_RISE * (1.0 + progress);
+
+        // Centered on the projected point, using the size from the last layout
+        match camera.world_to_viewport(camera_transform, world_position) {
+            Some(screen_position) => {
+                let size = node.size();
+                style.left = Val::Px(screen_position.x - size.x / 2.0);
+                style.top = Val::Px(screen_position.y - size.y / 2.0);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+
+        // Pops in slightly larger, then fades out
+        let pop = 1.0 + 0.3 * (1.0 - progress / 0.15).max(0.0);
+        for section in text.sections.iter_mut() {
+            section.style.font_size = floating.font_size * ui_scale * pop;
+            section.style.color.set_alpha(1.0 - progress);
+        }
+    }
+}
+
+// Glitter around the player while a shot is held at full draw
+const CHARGE_SPARKLE_INTERVAL: f32 = 0.04;
+
+fn emit_charge_sparkles(
+    player: Query<(&Transform, &Player)>,
+    mut spawn_particles_events: EventWriter<SpawnParticles>,
+    mut since_sparkle: Local<f32>,
+    time: Res<Time>,
+) {
+    let (transform, player) = player.single();
+    if !player.is_charging || player.charge_ratio() < 1.0 {
+        *since_sparkle = 0.0;
+        return;
+    }
+
+    *since_sparkle += time.delta_seconds();
+    while *since_sparkle >= CHARGE_SPARKLE_INTERVAL {
+        *since_sparkle -= CHARGE_SPARKLE_INTERVAL;
+        spawn_particles_events.send(SpawnParticles {
+            emitter: ParticleEmitter::charge_sparkle(ChargeTier::Power.color()),
+            position: transform.translation,
+            direction: Vec3::Y,
+        });
+    }
+}
+
+fn emit_particle_trails(
+    mut trails: Query<(&Transform, &mut ParticleTrail)>,
+    mut spawn_particles_events: EventWriter<SpawnParticles>,
+    time: Res<Time>,
+) {
+    for (transform, mut trail) in trails.iter_mut() {
+        trail.interval.tick(time.delta());
+        for _ in 0..trail.interval.times_finished_this_tick() {
+            spawn_particles_events.send(SpawnParticles {
+                emitter: trail.emitter,
+                position: transform.translation,
+                direction: transform.back().as_vec3(),
+            });
+        }
+    }
+}
+
+// Hand out pooled particle entities for every requested burst, only spawning new ones
+// while the pool is below its limit
+fn spawn_particles(
+    mut commands: Commands,
+    mut spawn_particles_events: EventReader<SpawnParticles>,
+    mut particle_pool: ResMut<ParticlePool>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Cosmetic only, so the seeded run generator is left alone
+    let mut rng = rand::thread_rng();
+
+    for event in spawn_particles_events.read() {
+        for _ in 0..event.emitter.count {
+            let (offset, particle) = event.emitter.particle(&mut rng, event.direction);
+            let transform = Transform::from_translation(event.position + offset)
+                .with_scale(Vec3::splat(particle.size()));
+
+            if let Some(entity) = particle_pool.free.pop() {
+                let material = particle_pool.fade_material(&mut materials, &particle);
+                commands.entity(entity).insert((
+                    particle,
+                    transform,
+                    material,
+                    Visibility::Inherited,
+                ));
+            } else if particle_pool.count < MAX_PARTICLES {
+                particle_pool.count += 1;
+                commands.spawn((
+                    PbrBundle {
+                        mesh: particle_pool.mesh.clone(),
+                        material: particle_pool.fade_material(&mut materials, &particle),
+                        transform,
+                        ..default()
+                    },
+                    particle,
+                    NotShadowCaster,
+                ));
+            }
+        }
+    }
+}
+
+// Move, shrink and fade particles, returning them to the pool when they die
+fn update_particles(
+    mut particles: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<StandardMaterial>,
+    )>,
+    mut particle_pool: ResMut<ParticlePool>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut particle, mut transform, mut visibility, mut material) in particles.iter_mut()
+    {
+        if !particle.is_alive() {
+            continue;
+        }
+
+        let fade_step = particle.fade_step();
+        particle.age += delta;
+        if !particle.is_alive() {
+            *visibility = Visibility::Hidden;
+            particle_pool.free.push(entity);
+            continue;
+        }
+
+        let (gravity, drag) = (particle.gravity, particle.drag);
+        particle.velocity.y -= gravity * delta;
+        particle.velocity *= (1.0 - drag * delta).max(0.0);
+        transform.translation += particle.velocity * delta;
+        transform.scale = Vec3::splat(particle.size());
+        // Only swap materials when the particle fades into the next step
+        if particle.fade_step() != fade_step {
+            *material = particle_pool.fade_material(&mut materials, &particle);
+        }
     }
 }
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use bevy::{color::ColorToPacked, prelude::*};
+use rand::Rng;
+
+// Particles beyond this many alive at once are dropped instead of spawned
+pub const MAX_PARTICLES: usize = 600;
+// Particles fade through this many shared materials instead of each owning one
+pub const FADE_STEPS: usize = 8;
+// Shatter colors are snapped to this many levels per channel, so enemies' random colors
+// share a bounded set of fade materials
+const SHATTER_COLOR_LEVELS: f32 = 6.0;
+
+// Describes a burst of particles, each particle picks its values at random within the ranges
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleEmitter {
+    pub count: u32,
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    pub spread: f32, // Angle around the emit direction in radians, PI sprays everywhere
+    pub gravity: f32, // Downwards acceleration
+    pub drag: f32,   // Fraction of the velocity lost per second
+    pub offset: f32, // Particles start up to this far from the emit position
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+}
+
+impl ParticleEmitter {
+    // Splinters where an arrow strikes an enemy
+    pub fn impact(color: Color) -> Self {
+        Self {
+            count: 10,
+            lifetime: (0.2, 0.4),
+            speed: (2.0, 5.0),
+            spread: 0.9,
+            gravity: 6.0,
+            drag: 2.0,
+            offset: 0.0,
+            start_color: color,
+            end_color: color.with_alpha(0.0),
+            start_size: 0.06,
+            end_size: 0.02,
+        }
+    }
+
+    // Small cubes left behind when an enemy shatters
+    pub fn shatter(color: Color) -> Self {
+        let snap = |channel: f32| {
+            (channel * (SHATTER_COLOR_LEVELS - 1.0)).round() / (SHATTER_COLOR_LEVELS - 1.0)
+        };
+        let color = color.to_srgba();
+        let color = Color::srgb(snap(color.red), snap(color.green), snap(color.blue));
+        Self {
+            count: 24,
+            lifetime: (0.6, 1.1),
+            speed: (1.5, 4.0),
+            spread: PI,
+            gravity: 9.8,
+            drag: 0.5,
+            offset: 0.25,
+            start_color: color,
+            end_color: color.with_alpha(0.0),
+            start_size: 0.12,
+            end_size: 0.04,
+        }
+    }
+
+    // Glitter drifting up around the player while the bow is fully drawn
+    pub fn charge_sparkle(color: Color) -> Self {
+        Self {
+            count: 2,
+            lifetime: (0.3, 0.6),
+            speed: (0.3, 0.8),
+            spread: 0.6,
+            gravity: -1.0,
+            drag: 1.0,
+            offset: 0.4,
+            start_color: Color::WHITE,
+            end_color: color.with_alpha(0.0),
+            start_size: 0.05,
+            end_size: 0.0,
+        }
+    }
+
+    // Embers shed behind a power shot in flight
+    pub fn trail(color: Color) -> Self {
+        Self {
+            count: 1,
+            lifetime: (0.25, 0.45),
+            speed: (0.1, 0.4),
+            spread: PI,
+            gravity: 0.0,
+            drag: 3.0,
+            offset: 0.05,
+            start_color: color,
+            end_color: color.with_alpha(0.0),
+            start_size: 0.1,
+            end_size: 0.0,
+        }
+    }
+
+    // Roll a particle for this emitter, `direction` is the center of the spread
+    pub fn particle(&self, rng: &mut impl Rng, direction: Vec3) -> (Vec3, Particle) {
+        let direction = direction.try_normalize().unwrap_or(Vec3::Y);
+        let heading = random_cone_direction(rng, direction, self.spread);
+        let offset = random_cone_direction(rng, Vec3::Y, PI) * rng.gen_range(0.0..=self.offset);
+        let speed = rng.gen_range(self.speed.0..=self.speed.1);
+
+        (
+            offset,
+            Particle {
+                velocity: heading * speed,
+                gravity: self.gravity,
+                drag: self.drag,
+                age: 0.0,
+                lifetime: rng.gen_range(self.lifetime.0..=self.lifetime.1),
+                start_color: self.start_color,
+                end_color: self.end_color,
+                start_size: self.start_size,
+                end_size: self.end_size,
+            },
+        )
+    }
+}
+
+// Uniformly random direction within `angle` of `axis`
+fn random_cone_direction(rng: &mut impl Rng, axis: Vec3, angle: f32) -> Vec3 {
+    let cos_angle = angle.min(PI).cos();
+    let z = rng.gen_range(cos_angle..=1.0);
+    let around = rng.gen_range(0.0..2.0 * PI);
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let local = Vec3::new(radius * around.cos(), radius * around.sin(), z);
+    Quat::from_rotation_arc(Vec3::Z, axis) * local
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Particle {
+    pub velocity: Vec3,
+    pub gravity: f32,
+    pub drag: f32,
+    pub age: f32,
+    pub lifetime: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+}
+
+impl Particle {
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    // From 0.0 when spawned to 1.0 when it dies
+    pub fn life_fraction(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+
+    // Which of the shared fade materials the particle is on
+    pub fn fade_step(&self) -> usize {
+        ((self.life_fraction() * FADE_STEPS as f32) as usize).min(FADE_STEPS - 1)
+    }
+
+    fn color_at(&self, t: f32) -> Color {
+        let start = LinearRgba::from(self.start_color);
+        let end = LinearRgba::from(self.end_color);
+        Color::LinearRgba(start * (1.0 - t) + end * t)
+    }
+
+    pub fn size(&self) -> f32 {
+        self.start_size.lerp(self.end_size, self.life_fraction())
+    }
+}
+
+// Keeps emitting bursts while the entity it is on exists, e.g. trails behind projectiles
+#[derive(Component)]
+pub struct ParticleTrail {
+    pub emitter: ParticleEmitter,
+    pub interval: Timer,
+}
+
+impl ParticleTrail {
+    pub fn new(emitter: ParticleEmitter, interval_secs: f32) -> Self {
+        Self {
+            emitter,
+            interval: Timer::from_seconds(interval_secs, TimerMode::Repeating),
+        }
+    }
+}
+
+// Start and end colors of a particle, the particles sharing them share fade materials
+type FadeKey = ([u8; 4], [u8; 4]);
+
+// Particle entities are hidden and reused once they die rather than despawned
+#[derive(Resource)]
+pub struct ParticlePool {
+    pub mesh: Handle<Mesh>,
+    pub free: Vec<Entity>,
+    pub count: usize, // Pooled entities, alive or free
+    // Fade materials shared by every particle with the same start and end colors
+    pub fade_materials: HashMap<FadeKey, Vec<Handle<StandardMaterial>>>,
+}
+
+impl FromWorld for ParticlePool {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        Self {
+            mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+            free: Vec::new(),
+            count: 0,
+            fade_materials: HashMap::new(),
+        }
+    }
+}
+
+impl ParticlePool {
+    // Forget every pooled entity, for when they have all been despawned
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.count = 0;
+    }
+
+    // The shared material for the particle's current fade step, made the first time its
+    // colors are seen
+    pub fn fade_material(
+        &mut self,
+        materials: &mut Assets<StandardMaterial>,
+        particle: &Particle,
+    ) -> Handle<StandardMaterial> {
+        let key: FadeKey = (
+            particle.start_color.to_srgba().to_u8_array(),
+            particle.end_color.to_srgba().to_u8_array(),
+        );
+        let steps = self.fade_materials.entry(key).or_insert_with(|| {
+            (0..FADE_STEPS)
+                .map(|step| {
+                    materials.add(StandardMaterial {
+                        base_color: particle.color_at(step as f32 / (FADE_STEPS - 1) as f32),
+                        unlit: true,
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    })
+                })
+                .collect()
+        });
+        steps[particle.fade_step()].clone()
+    }
+}
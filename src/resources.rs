@@ -1,4 +1,134 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, time::Stopwatch};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
+#[derive(Resource, Default)]
+pub struct SettingsMenuSelection(pub usize);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    // Any contact with an enemy ends the run
+    Hardcore,
+}
+
+impl GameMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Normal => "Normal",
+            GameMode::Hardcore => "Hardcore",
+        }
+    }
+}
+
+// Information about the current run, recorded alongside the score
 #[derive(Resource)]
-pub struct PowerShotCooldownTimer(pub Timer);
+pub struct RunInfo {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub duration: Stopwatch,
+}
+
+// Random number generator seeded from RunInfo::seed
+#[derive(Resource)]
+pub struct RunRng(pub StdRng);
+
+// Present while the player is typing their name for the leaderboard
+#[derive(Resource, Default)]
+pub struct NameEntry {
+    pub name: String,
+}
+
+// Rank of the most recently submitted leaderboard entry
+#[derive(Resource, Default)]
+pub struct LeaderboardHighlight(pub Option<usize>);
+
+// Counters for the current run, exported once it ends
+#[derive(Resource, Serialize, Clone, Debug, Default)]
+pub struct RunStats {
+    pub quick_shots: u32,
+    pub medium_shots: u32,
+    pub power_shots: u32,
+    pub hits: u32,
+    pub arrows_landed: u32, // Arrows that hit at least one enemy
+    pub kills: u32,
+    pub ability_shots: u32, // Arrows fired by abilities other than the power shot
+    pub distance_walked: f32,
+    pub time_survived: f32,
+    pub closest_call: Option<f32>, // Smallest distance between the player and an enemy
+    #[serde(skip)]
+    pub last_player_position: Option<Vec3>,
+}
+
+impl RunStats {
+    pub fn shots_fired(&self) -> u32 {
+        self.quick_shots + self.medium_shots + self.power_shots + self.ability_shots
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired() == 0 {
+            0.0
+        } else {
+            self.arrows_landed as f32 / self.shots_fired() as f32
+        }
+    }
+}
+
+// Consecutive hits within the combo window raise the score multiplier
+#[derive(Resource)]
+pub struct Combo {
+    pub streak: u32,
+    pub window: Timer,
+}
+
+impl Combo {
+    pub const WINDOW_SECS: f32 = 2.5;
+    pub const HITS_PER_LEVEL: u32 = 3;
+    pub const MAX_MULTIPLIER: u32 = 5;
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak / Self::HITS_PER_LEVEL).min(Self::MAX_MULTIPLIER)
+    }
+
+    pub fn register_hit(&mut self) {
+        self.streak += 1;
+        self.window.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.streak = 0;
+    }
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            streak: 0,
+            window: Timer::from_seconds(Self::WINDOW_SECS, TimerMode::Once),
+        }
+    }
+}
+
+// Time is slowed down while this runs out, counted in real time so the slowdown ends
+#[derive(Resource, Default)]
+pub struct HitStop {
+    pub remaining: f32,
+}
+
+// Floating text nodes are hidden and reused once they finish rather than despawned
+#[derive(Resource, Default)]
+pub struct FloatingTextPool {
+    pub free: Vec<Entity>,
+    pub count: usize, // Pooled entities, active or free
+}
+
+impl FloatingTextPool {
+    pub const MAX_TEXTS: usize = 48;
+
+    // Forget every pooled entity, for when they have all been despawned
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.count = 0;
+    }
+}
@@ -0,0 +1,527 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{stats::StatsExportFormat, MAX_HEIGHT, MAX_WIDTH, MIN_HEIGHT, MIN_WIDTH};
+
+const CONFIG_DIRECTORY: &str = "archery-minigame";
+const SETTINGS_FILE: &str = "settings.ron";
+
+// Resolutions the settings menu cycles through, all with the default aspect ratio
+const RESOLUTION_PRESETS: [(f32, f32); 4] = [
+    (MIN_WIDTH, MIN_HEIGHT),
+    (1280.0, 1280.0 / (MAX_WIDTH / MAX_HEIGHT)),
+    (MAX_WIDTH, MAX_HEIGHT),
+    (1920.0, 1920.0 / (MAX_WIDTH / MAX_HEIGHT)),
+];
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub input: InputSettings,
+    pub audio: AudioSettings,
+    pub accessibility: AccessibilitySettings,
+    pub gameplay: GameplaySettings,
+    pub crosshair: CrosshairSettings,
+    pub minimap: MinimapSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub resolution: (f32, f32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub camera_mode: CameraMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    ThirdPerson,
+    // Camera at the player's eyes, with the player hidden and a bow in view instead
+    FirstPerson,
+}
+
+impl CameraMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CameraMode::ThirdPerson => "Third person",
+            CameraMode::FirstPerson => "First person",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+        }
+    }
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            resolution: (MAX_WIDTH, MAX_HEIGHT),
+            fullscreen: false,
+            vsync: true,
+            camera_mode: CameraMode::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct InputSettings {
+    pub movement_speed: f32,
+    pub rotation_speed: f32,       // Rotation speed in radians per second
+    pub aim_offset: f32,           // How far to the right of the camera's forward the arrows fly
+    pub move_while_charging: bool, // Walk slowly while drawing the bow instead of standing still
+    pub aim_zoom: f32,             // How much drawing the bow zooms in, 0.0 disables it
+    pub aim_zoom_curve: ZoomCurve, // How the zoom follows the draw
+    pub aim_sensitivity: f32,      // Rotation speed multiplier when fully zoomed in
+    pub trajectory_preview: bool,  // Show where the arrow will fly while drawing the bow
+}
+
+// Maps the draw strength to the aim zoom
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZoomCurve {
+    Linear,
+    // Barely zooms at first, most of it happens near a full draw
+    EaseIn,
+    // Zooms in quickly, then settles
+    EaseOut,
+    #[default]
+    Smooth,
+}
+
+impl ZoomCurve {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            ZoomCurve::Linear => t,
+            ZoomCurve::EaseIn => t * t,
+            ZoomCurve::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            ZoomCurve::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ZoomCurve::Linear => "Linear",
+            ZoomCurve::EaseIn => "Ease in",
+            ZoomCurve::EaseOut => "Ease out",
+            ZoomCurve::Smooth => "Smooth",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ZoomCurve::Linear => ZoomCurve::EaseIn,
+            ZoomCurve::EaseIn => ZoomCurve::EaseOut,
+            ZoomCurve::EaseOut => ZoomCurve::Smooth,
+            ZoomCurve::Smooth => ZoomCurve::Linear,
+        }
+    }
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            movement_speed: 2.5,
+            rotation_speed: 1.0,
+            aim_offset: 0.03,
+            move_while_charging: true,
+            aim_zoom: 1.0,
+            aim_zoom_curve: ZoomCurve::default(),
+            aim_sensitivity: 0.5,
+            trajectory_preview: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { master_volume: 1.0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub ui_scale: f32,
+    pub reduced_motion: bool, // Also turns off screen shake and hit-stop
+    pub screen_shake: f32,    // Camera shake intensity, 0.0 disables it
+    pub hit_stop: bool,       // Briefly slow time down on charged-shot kills
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            reduced_motion: false,
+            screen_shake: 1.0,
+            hit_stop: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GameplaySettings {
+    pub max_charge_duration: f32,
+    pub enemy_spawn_interval: f32,
+    pub stats_export: StatsExportFormat,
+    pub hardcore: bool,     // One-touch death, takes effect on the next run
+    pub health_regen: bool, // Slowly heal after a while without taking damage
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        Self {
+            max_charge_duration: 1.0,
+            enemy_spawn_interval: 2.0,
+            stats_export: StatsExportFormat::default(),
+            hardcore: false,
+            health_regen: true,
+        }
+    }
+}
+
+// Crosshair shape, in units of half the crosshair's size
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CrosshairSettings {
+    pub thickness: f32,
+    pub length: f32,
+    pub gap: f32,
+    pub dot: f32,
+    pub outline: f32,
+    pub dynamic: bool, // Spread while moving and tighten while charging
+}
+
+impl Default for CrosshairSettings {
+    fn default() -> Self {
+        Self {
+            thickness: 0.02,
+            length: 0.35,
+            gap: 0.1,
+            dot: 0.0,
+            outline: 0.02,
+            dynamic: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MinimapSettings {
+    pub radius: f32, // Radius of the minimap in the reference resolution
+    pub zoom: f32,   // 1.0 shows 15 units around the player
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            radius: 100.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIRECTORY).join(SETTINGS_FILE))
+    }
+
+    // Load the settings from the config file, falling back to the defaults if it is missing or invalid
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|error| {
+                warn!(
+                    "Ignoring invalid settings file {}: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        settings.validate();
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No config directory available, settings will not be saved");
+            return;
+        };
+
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+                }
+                fs::write(&path, contents).map_err(|error| error.to_string())
+            });
+
+        if let Err(error) = result {
+            warn!("Failed to save settings to {}: {}", path.display(), error);
+        }
+    }
+
+    // Clamp every value into a range the game can work with
+    pub fn validate(&mut self) {
+        let (width, height) = self.display.resolution;
+        self.display.resolution = (
+            if width.is_finite() {
+                width.max(MIN_WIDTH)
+            } else {
+                MAX_WIDTH
+            },
+            if height.is_finite() {
+                height.max(MIN_HEIGHT)
+            } else {
+                MAX_HEIGHT
+            },
+        );
+
+        self.input.movement_speed = clamp_or(self.input.movement_speed, 0.5, 10.0, 2.5);
+        self.input.rotation_speed = clamp_or(self.input.rotation_speed, 0.1, 5.0, 1.0);
+        self.input.aim_offset = clamp_or(self.input.aim_offset, -0.2, 0.2, 0.03);
+        self.input.aim_zoom = clamp_or(self.input.aim_zoom, 0.0, 1.0, 1.0);
+        self.input.aim_sensitivity = clamp_or(self.input.aim_sensitivity, 0.1, 1.0, 0.5);
+
+        self.audio.master_volume = clamp_or(self.audio.master_volume, 0.0, 1.0, 1.0);
+
+        self.accessibility.ui_scale = clamp_or(self.accessibility.ui_scale, 0.5, 2.0, 1.0);
+        self.accessibility.screen_shake = clamp_or(self.accessibility.screen_shake, 0.0, 2.0, 1.0);
+
+        self.gameplay.max_charge_duration =
+            clamp_or(self.gameplay.max_charge_duration, 0.25, 3.0, 1.0);
+        self.gameplay.enemy_spawn_interval =
+            clamp_or(self.gameplay.enemy_spawn_interval, 0.5, 10.0, 2.0);
+
+        self.crosshair.thickness = clamp_or(self.crosshair.thickness, 0.005, 0.1, 0.02);
+        self.crosshair.length = clamp_or(self.crosshair.length, 0.0, 0.5, 0.35);
+        self.crosshair.gap = clamp_or(self.crosshair.gap, 0.0, 0.3, 0.1);
+        self.crosshair.dot = clamp_or(self.crosshair.dot, 0.0, 0.1, 0.0);
+        self.crosshair.outline = clamp_or(self.crosshair.outline, 0.0, 0.05, 0.02);
+
+        self.minimap.radius = clamp_or(self.minimap.radius, 50.0, 180.0, 100.0);
+        self.minimap.zoom = clamp_or(self.minimap.zoom, 0.5, 3.0, 1.0);
+    }
+}
+
+fn clamp_or(value: f32, min: f32, max: f32, fallback: f32) -> f32 {
+    if value.is_finite() {
+        value.clamp(min, max)
+    } else {
+        fallback
+    }
+}
+
+// Every option that can be changed from the settings menu, in display order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsOption {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    CameraMode,
+    MovementSpeed,
+    RotationSpeed,
+    AimOffset,
+    MoveWhileCharging,
+    AimZoom,
+    AimZoomCurve,
+    AimSensitivity,
+    TrajectoryPreview,
+    MasterVolume,
+    UiScale,
+    ReducedMotion,
+    ScreenShake,
+    HitStop,
+    MaxChargeDuration,
+    EnemySpawnInterval,
+    StatsExport,
+    Hardcore,
+    HealthRegen,
+    MinimapRadius,
+    MinimapZoom,
+}
+
+impl SettingsOption {
+    pub const ALL: [SettingsOption; 24] = [
+        SettingsOption::Resolution,
+        SettingsOption::Fullscreen,
+        SettingsOption::Vsync,
+        SettingsOption::CameraMode,
+        SettingsOption::MovementSpeed,
+        SettingsOption::RotationSpeed,
+        SettingsOption::AimOffset,
+        SettingsOption::MoveWhileCharging,
+        SettingsOption::AimZoom,
+        SettingsOption::AimZoomCurve,
+        SettingsOption::AimSensitivity,
+        SettingsOption::TrajectoryPreview,
+        SettingsOption::MasterVolume,
+        SettingsOption::UiScale,
+        SettingsOption::ReducedMotion,
+        SettingsOption::ScreenShake,
+        SettingsOption::HitStop,
+        SettingsOption::MaxChargeDuration,
+        SettingsOption::EnemySpawnInterval,
+        SettingsOption::StatsExport,
+        SettingsOption::Hardcore,
+        SettingsOption::HealthRegen,
+        SettingsOption::MinimapRadius,
+        SettingsOption::MinimapZoom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsOption::Resolution => "Resolution",
+            SettingsOption::Fullscreen => "Fullscreen",
+            SettingsOption::Vsync => "VSync",
+            SettingsOption::CameraMode => "Camera",
+            SettingsOption::MovementSpeed => "Movement speed",
+            SettingsOption::RotationSpeed => "Rotation speed",
+            SettingsOption::AimOffset => "Aim offset",
+            SettingsOption::MoveWhileCharging => "Move while charging",
+            SettingsOption::AimZoom => "Aim zoom",
+            SettingsOption::AimZoomCurve => "Aim zoom curve",
+            SettingsOption::AimSensitivity => "Aim sensitivity",
+            SettingsOption::TrajectoryPreview => "Trajectory preview",
+            SettingsOption::MasterVolume => "Master volume",
+            SettingsOption::UiScale => "UI scale",
+            SettingsOption::ReducedMotion => "Reduced motion",
+            SettingsOption::ScreenShake => "Screen shake",
+            SettingsOption::HitStop => "Hit-stop",
+            SettingsOption::MaxChargeDuration => "Max charge time",
+            SettingsOption::EnemySpawnInterval => "Enemy spawn interval",
+            SettingsOption::StatsExport => "Export run stats",
+            SettingsOption::Hardcore => "Hardcore (next run)",
+            SettingsOption::HealthRegen => "Health regen",
+            SettingsOption::MinimapRadius => "Minimap radius",
+            SettingsOption::MinimapZoom => "Minimap zoom",
+        }
+    }
+
+    pub fn value(&self, settings: &Settings) -> String {
+        match self {
+            SettingsOption::Resolution => format!(
+                "{:.0}x{:.0}",
+                settings.display.resolution.0, settings.display.resolution.1
+            ),
+            SettingsOption::Fullscreen => on_off(settings.display.fullscreen),
+            SettingsOption::Vsync => on_off(settings.display.vsync),
+            SettingsOption::CameraMode => settings.display.camera_mode.label().to_string(),
+            SettingsOption::MovementSpeed => format!("{:.1}", settings.input.movement_speed),
+            SettingsOption::RotationSpeed => format!("{:.1}", settings.input.rotation_speed),
+            SettingsOption::AimOffset => format!("{:.2}", settings.input.aim_offset),
+            SettingsOption::MoveWhileCharging => on_off(settings.input.move_while_charging),
+            SettingsOption::AimZoom => percent(settings.input.aim_zoom),
+            SettingsOption::AimZoomCurve => settings.input.aim_zoom_curve.label().to_string(),
+            SettingsOption::AimSensitivity => percent(settings.input.aim_sensitivity),
+            SettingsOption::TrajectoryPreview => on_off(settings.input.trajectory_preview),
+            SettingsOption::MasterVolume => percent(settings.audio.master_volume),
+            SettingsOption::UiScale => percent(settings.accessibility.ui_scale),
+            SettingsOption::ReducedMotion => on_off(settings.accessibility.reduced_motion),
+            SettingsOption::ScreenShake => percent(settings.accessibility.screen_shake),
+            SettingsOption::HitStop => on_off(settings.accessibility.hit_stop),
+            SettingsOption::MaxChargeDuration => {
+                format!("{:.2}s", settings.gameplay.max_charge_duration)
+            }
+            SettingsOption::EnemySpawnInterval => {
+                format!("{:.1}s", settings.gameplay.enemy_spawn_interval)
+            }
+            SettingsOption::StatsExport => settings.gameplay.stats_export.label().to_string(),
+            SettingsOption::Hardcore => on_off(settings.gameplay.hardcore),
+            SettingsOption::HealthRegen => on_off(settings.gameplay.health_regen),
+            SettingsOption::MinimapRadius => format!("{:.0}", settings.minimap.radius),
+            SettingsOption::MinimapZoom => format!("{:.2}x", settings.minimap.zoom),
+        }
+    }
+
+    // Step the option up (direction > 0) or down (direction < 0)
+    pub fn adjust(&self, settings: &mut Settings, direction: f32) {
+        match self {
+            SettingsOption::Resolution => {
+                let current = RESOLUTION_PRESETS
+                    .iter()
+                    .position(|preset| *preset == settings.display.resolution)
+                    .unwrap_or(2) as i32;
+                let next = (current + direction.signum() as i32)
+                    .rem_euclid(RESOLUTION_PRESETS.len() as i32);
+                settings.display.resolution = RESOLUTION_PRESETS[next as usize];
+            }
+            SettingsOption::Fullscreen => {
+                settings.display.fullscreen = !settings.display.fullscreen
+            }
+            SettingsOption::Vsync => settings.display.vsync = !settings.display.vsync,
+            SettingsOption::CameraMode => {
+                settings.display.camera_mode = settings.display.camera_mode.next()
+            }
+            SettingsOption::MovementSpeed => settings.input.movement_speed += direction * 0.5,
+            SettingsOption::RotationSpeed => settings.input.rotation_speed += direction * 0.1,
+            SettingsOption::AimOffset => settings.input.aim_offset += direction * 0.01,
+            SettingsOption::MoveWhileCharging => {
+                settings.input.move_while_charging = !settings.input.move_while_charging
+            }
+            SettingsOption::AimZoom => settings.input.aim_zoom += direction * 0.1,
+            SettingsOption::AimZoomCurve => {
+                settings.input.aim_zoom_curve = settings.input.aim_zoom_curve.next()
+            }
+            SettingsOption::AimSensitivity => settings.input.aim_sensitivity += direction * 0.1,
+            SettingsOption::TrajectoryPreview => {
+                settings.input.trajectory_preview = !settings.input.trajectory_preview
+            }
+            SettingsOption::MasterVolume => settings.audio.master_volume += direction * 0.1,
+            SettingsOption::UiScale => settings.accessibility.ui_scale += direction * 0.1,
+            SettingsOption::ReducedMotion => {
+                settings.accessibility.reduced_motion = !settings.accessibility.reduced_motion
+            }
+            SettingsOption::ScreenShake => settings.accessibility.screen_shake += direction * 0.25,
+            SettingsOption::HitStop => {
+                settings.accessibility.hit_stop = !settings.accessibility.hit_stop
+            }
+            SettingsOption::MaxChargeDuration => {
+                settings.gameplay.max_charge_duration += direction * 0.25
+            }
+            SettingsOption::EnemySpawnInterval => {
+                settings.gameplay.enemy_spawn_interval += direction * 0.5
+            }
+            SettingsOption::StatsExport => {
+                settings.gameplay.stats_export = settings.gameplay.stats_export.next()
+            }
+            SettingsOption::Hardcore => settings.gameplay.hardcore = !settings.gameplay.hardcore,
+            SettingsOption::HealthRegen => {
+                settings.gameplay.health_regen = !settings.gameplay.health_regen
+            }
+            SettingsOption::MinimapRadius => settings.minimap.radius += direction * 10.0,
+            SettingsOption::MinimapZoom => settings.minimap.zoom += direction * 0.25,
+        }
+        settings.validate();
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "On" } else { "Off" }.to_string()
+}
+
+fn percent(value: f32) -> String {
+    format!("{:.0}%", value * 100.0)
+}
@@ -6,4 +6,6 @@ pub enum GameState {
     Playing,
     GameOver,
     Restarting,
+    Settings,
+    Leaderboard,
 }
@@ -0,0 +1,158 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::{GameMode, RunStats};
+
+const DATA_DIRECTORY: &str = "archery-minigame";
+const RUNS_DIRECTORY: &str = "runs";
+const RUNS_CSV_FILE: &str = "runs.csv";
+// New columns go at the end, files with any other header are moved aside
+const RUNS_CSV_HEADER: &str = "timestamp,seed,mode,score,accuracy,quick_shots,medium_shots,\
+                               power_shots,hits,arrows_landed,kills,\
+                               distance_walked,time_survived,closest_call,ability_shots";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatsExportFormat {
+    Off,
+    #[default]
+    Json,
+    Csv,
+}
+
+impl StatsExportFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsExportFormat::Off => "Off",
+            StatsExportFormat::Json => "JSON",
+            StatsExportFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            StatsExportFormat::Off => StatsExportFormat::Json,
+            StatsExportFormat::Json => StatsExportFormat::Csv,
+            StatsExportFormat::Csv => StatsExportFormat::Off,
+        }
+    }
+}
+
+// Everything recorded about a single finished run
+#[derive(Serialize, Debug)]
+pub struct RunRecord {
+    pub timestamp: u64,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub score: u32,
+    pub accuracy: f32,
+    pub stats: RunStats,
+}
+
+impl RunRecord {
+    pub fn summary(&self) -> String {
+        let stats = &self.stats;
+        let closest_call = match stats.closest_call {
+            Some(distance) => format!("{:.2}m", distance),
+            None => "-".to_string(),
+        };
+
+        [
+            "Run Summary".to_string(),
+            format!("Time survived: {:.1}s", stats.time_survived),
+            format!(
+                "Shots: {} ({} quick / {} medium / {} power / {} ability)",
+                stats.shots_fired(),
+                stats.quick_shots,
+                stats.medium_shots,
+                stats.power_shots,
+                stats.ability_shots
+            ),
+            format!("Hits: {}", stats.hits),
+            format!("Accuracy: {:.0}%", self.accuracy * 100.0),
+            format!("Kills: {}", stats.kills),
+            format!("Distance walked: {:.1}m", stats.distance_walked),
+            format!("Closest call: {}", closest_call),
+        ]
+        .join("\n")
+    }
+
+    pub fn export(&self, format: StatsExportFormat) {
+        let Some(directory) = dirs::data_dir().map(|dir| dir.join(DATA_DIRECTORY)) else {
+            warn!("No data directory available, run statistics will not be exported");
+            return;
+        };
+
+        let result = match format {
+            StatsExportFormat::Off => return,
+            StatsExportFormat::Json => self.export_json(directory.join(RUNS_DIRECTORY)),
+            StatsExportFormat::Csv => self.export_csv(directory.join(RUNS_CSV_FILE)),
+        };
+
+        if let Err(error) = result {
+            warn!("Failed to export run statistics: {}", error);
+        }
+    }
+
+    // One pretty-printed file per run
+    fn export_json(&self, directory: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&directory)?;
+        let path = directory.join(format!("run-{}-{:x}.json", self.timestamp, self.seed));
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // One row per run appended to a single file, with a header when it is first created.
+    // A file written with different columns is renamed so its rows keep matching its header
+    fn export_csv(&self, path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            if contents.lines().next() != Some(RUNS_CSV_HEADER) {
+                fs::rename(
+                    &path,
+                    path.with_extension(format!("{}.csv", self.timestamp)),
+                )?;
+            }
+        }
+
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if is_new {
+            writeln!(file, "{}", RUNS_CSV_HEADER)?;
+        }
+
+        let stats = &self.stats;
+        writeln!(
+            file,
+            "{},{},{:?},{},{:.4},{},{},{},{},{},{},{:.3},{:.3},{},{}",
+            self.timestamp,
+            self.seed,
+            self.mode,
+            self.score,
+            self.accuracy,
+            stats.quick_shots,
+            stats.medium_shots,
+            stats.power_shots,
+            stats.hits,
+            stats.arrows_landed,
+            stats.kills,
+            stats.distance_walked,
+            stats.time_survived,
+            stats
+                .closest_call
+                .map(|distance| format!("{:.3}", distance))
+                .unwrap_or_default(),
+            stats.ability_shots
+        )?;
+        Ok(())
+    }
+}